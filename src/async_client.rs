@@ -0,0 +1,194 @@
+use error::*;
+use futures::{future, Future, Stream};
+use futures::future::Loop;
+use protocol::Protocol;
+use requests::Request;
+use responses::Response;
+use reqwest::{StatusCode, Url};
+use reqwest::async::{Chunk, Client, Response as HttpResponse};
+use reqwest::header::CONTENT_TYPE;
+use serde_json::{self, Value};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use trace::CallSpan;
+use {MAX_SESSION_RENEGOTIATIONS, SESSION_HEADER};
+
+/// A future returned by `AsyncTransmission`. It doesn't borrow the client,
+/// so it can be spawned on a multi-threaded runtime.
+pub type ResponseFuture<T> = Box<Future<Item = T, Error = Error> + Send>;
+
+/// An asynchronous connection to the Transmission daemon. Works like `Transmission`
+/// but `send` returns a future and requests can be sent concurrently.
+/// The futures must be run on a `tokio` runtime.
+///
+/// Available with the `async` feature.
+pub struct AsyncTransmission {
+    shared: Arc<Shared>
+}
+
+/// The state shared with the futures of the requests in flight.
+struct Shared {
+    client:   Client,
+    auth:     Option<(String, String)>,
+    session:  Mutex<Option<String>>,
+    url:      Url,
+    tag:      AtomicUsize,
+    protocol: Protocol
+}
+
+/// Used by the setters when requests are in flight. The requests keep the old state.
+impl Clone for Shared {
+    fn clone(&self) -> Shared {
+        Shared {
+            client:   self.client.clone(),
+            auth:     self.auth.clone(),
+            session:  Mutex::new(self.session.lock().unwrap().clone()),
+            url:      self.url.clone(),
+            tag:      AtomicUsize::new(self.tag.load(Ordering::Relaxed)),
+            protocol: self.protocol
+        }
+    }
+}
+
+impl AsyncTransmission {
+    /// Create a new instance of `AsyncTransmission` using the default url
+    /// `http://127.0.0.1:9091/transmission/rpc` and no authentication.
+    pub fn new() -> AsyncTransmission {
+        AsyncTransmission {
+            shared: Arc::new(Shared {
+                client:   Client::new(),
+                auth:     None,
+                session:  Mutex::new(None),
+                url:      Url::parse("http://127.0.0.1:9091/transmission/rpc").unwrap(),
+                tag:      AtomicUsize::new(0),
+                protocol: Protocol::Legacy
+            })
+        }
+    }
+
+    /// Sets the url used to connect the daemon for future requests
+    pub fn set_url(&mut self, url: Url) -> &mut Self {
+        Arc::make_mut(&mut self.shared).url = url;
+        self
+    }
+
+    /// Sets the credentials to be used in future requests
+    pub fn set_auth(&mut self, username: String, password: String) -> &mut Self {
+        Arc::make_mut(&mut self.shared).auth = Some((username, password));
+        self
+    }
+
+    /// Sets the protocol used in future requests.
+    pub fn set_protocol(&mut self, protocol: Protocol) -> &mut Self {
+        Arc::make_mut(&mut self.shared).protocol = protocol;
+        self
+    }
+
+    /// Sends given request to the daemon and returns a future of the received response.
+    /// The request is encoded immediately, so the future doesn't borrow it.
+    pub fn send<R>(&self, request: &R) -> ResponseFuture<R::Response>
+        where R: Request, R::Response: Response + Send + 'static
+    {
+        let shared = self.shared.clone();
+        let tag = shared.tag.fetch_add(1, Ordering::Relaxed);
+        let method = request.method_name().to_string();
+
+        let span = CallSpan::new(&method, tag, &shared.url);
+        span.attempt(1);
+
+        let req_value = shared.protocol.encode(request, tag);
+        trace_debug!(request = %::trace::redact_arguments(req_value.clone()), "sending request");
+
+        let body = match serde_json::to_string(&req_value) {
+            Ok(body) => body,
+            Err(e) => {
+                let result = Err(Error::from(e).in_call(&method, tag, None));
+                span.finish(&result);
+                return Box::new(future::result(result));
+            }
+        };
+
+        Shared::respond(shared, body, method, tag, span)
+    }
+}
+
+impl Shared {
+    /// Exchanges the body and deserializes the response. Kept apart from `send`
+    /// so that the future doesn't depend on the type of the request.
+    fn respond<T>(shared: Arc<Shared>, body: String, method: String, tag: usize,
+                  span: CallSpan) -> ResponseFuture<T>
+        where T: Response + Send + 'static
+    {
+        Box::new(Shared::exchange(shared.clone(), body).then(move |result| {
+            let result = result.and_then(|(status, bytes)| {
+                span.response_size(bytes.len());
+
+                // See `Transmission::send_tagged` about including the response in the error.
+                shared.read_response(status, &bytes, tag)
+                    .and_then(|args| Ok(T::from_value(args)?))
+                    .map_err(|e| e.in_call(&method, tag, Some(&bytes[..])))
+            }).map_err(|e| e.in_call(&method, tag, None));
+
+            span.finish(&result);
+            result
+        }))
+    }
+
+    fn post(&self, body: String) -> ResponseFuture<HttpResponse> {
+        let mut builder = self.client.post(self.url.clone())
+            .header(CONTENT_TYPE, "application/json; charset=utf-8")
+            .body(body);
+
+        if let Some(ref session) = *self.session.lock().unwrap() {
+            builder = builder.header(SESSION_HEADER, session.as_str());
+        }
+
+        if let Some((ref username, ref password)) = self.auth {
+            builder = builder.basic_auth(username, Some(password));
+        }
+
+        Box::new(builder.send().map_err(Error::from))
+    }
+
+    /// Posts the body, renegotiating the session id up to `MAX_SESSION_RENEGOTIATIONS`
+    /// times like `Transmission::exchange`, and returns the status and the body of the response.
+    fn exchange(shared: Arc<Shared>, body: String) -> ResponseFuture<(StatusCode, Chunk)> {
+        Box::new(future::loop_fn(0, move |renegotiations| {
+            let shared = shared.clone();
+            shared.post(body.clone()).and_then(move |response| -> Result<Loop<HttpResponse, usize>> {
+                if response.status() != StatusCode::CONFLICT {
+                    return Ok(Loop::Break(response));
+                }
+                if renegotiations == MAX_SESSION_RENEGOTIATIONS {
+                    return Err(protocol::Error::SessionConflict(renegotiations).into());
+                }
+                trace_debug!(renegotiations, "renegotiating the session id");
+
+                let session = response.headers().get(SESSION_HEADER)
+                    .and_then(|v| v.to_str().ok())
+                    .map(|s| s.to_string())
+                    .ok_or(protocol::Error::MissingSessionId)?;
+                *shared.session.lock().unwrap() = Some(session);
+
+                Ok(Loop::Continue(renegotiations + 1))
+            })
+        }).and_then(|response| {
+            let status = response.status();
+            response.into_body().concat2()
+                .map(move |bytes| (status, bytes))
+                .map_err(Error::from)
+        }))
+    }
+
+    fn read_response(&self, status: StatusCode, bytes: &[u8], tag: usize) -> Result<Value> {
+        // Like the synchronous client, only 200 is a successful response.
+        if status != StatusCode::OK {
+            let status = ::hyper::status::StatusCode::from_u16(status.as_u16());
+            return Err(daemon::from_status(status, bytes).into());
+        }
+
+        let value: Value = serde_json::from_slice(bytes)?;
+        trace_debug!(response = %::trace::redact_arguments(value.clone()), "received response");
+        self.protocol.decode(value, tag)
+    }
+}
@@ -0,0 +1,112 @@
+//! Sending several requests concurrently.
+
+use crossbeam;
+use error::Result;
+use requests::Request;
+use responses::Response;
+use std::cmp;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use Transmission;
+
+/// A group of requests that are sent concurrently by `Transmission::send_batch`.
+///
+/// Implemented for tuples of up to eight requests of any type, and for slices of
+/// requests of the same type. The requests are sent by at most `limit` threads at a
+/// time and the connections are shared through the transport's pool. The responses
+/// are returned in the same order as the requests, and a failed request doesn't
+/// affect the others.
+pub trait Batch {
+    /// The results of the requests
+    type Responses;
+
+    fn send_all(&self, tr: &Transmission, limit: usize) -> Self::Responses;
+}
+
+/// The result of a request, filled in by the thread that sent it.
+struct Slot<T>(Mutex<Option<T>>);
+
+impl<T> Slot<T> {
+    fn new() -> Slot<T> {
+        Slot(Mutex::new(None))
+    }
+
+    fn fill(&self, value: T) {
+        *self.0.lock().unwrap() = Some(value);
+    }
+
+    fn take(self) -> T {
+        self.0.into_inner().unwrap().expect("A request of the batch was not sent.")
+    }
+}
+
+/// Calls `job` with the numbers from 0 to `count` using at most `limit` threads.
+fn run<F: Fn(usize) + Sync>(count: usize, limit: usize, job: F) {
+    let next = AtomicUsize::new(0);
+    let workers = cmp::min(cmp::max(limit, 1), count);
+
+    crossbeam::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                if i >= count {
+                    break;
+                }
+                job(i);
+            });
+        }
+    });
+}
+
+macro_rules! tuple_batch {
+    ($count:expr; $($request:ident $name:ident),+) => {
+        impl<$($request),+> Batch for ($($request,)+)
+            where $($request: Request + Sync, $request::Response: Response + Send),+
+        {
+            type Responses = ($(Result<$request::Response>,)+);
+
+            fn send_all(&self, tr: &Transmission, limit: usize) -> Self::Responses {
+                let ($(ref $name,)+) = *self;
+                // Each request is paired with the slot of its result.
+                $(let $name = ($name, Slot::new());)+
+
+                run($count, limit, |i| {
+                    // The requests are numbered in order.
+                    let mut numbers = 0..;
+                    $(if numbers.next() == Some(i) {
+                        $name.1.fill(tr.send($name.0));
+                    })+
+                });
+
+                ($($name.1.take(),)+)
+            }
+        }
+    }
+}
+
+tuple_batch!(1; A a);
+tuple_batch!(2; A a, B b);
+tuple_batch!(3; A a, B b, C c);
+tuple_batch!(4; A a, B b, C c, D d);
+tuple_batch!(5; A a, B b, C c, D d, E e);
+tuple_batch!(6; A a, B b, C c, D d, E e, F f);
+tuple_batch!(7; A a, B b, C c, D d, E e, F f, G g);
+tuple_batch!(8; A a, B b, C c, D d, E e, F f, G g, H h);
+
+impl<R> Batch for [R] where R: Request + Sync, R::Response: Response + Send {
+    type Responses = Vec<Result<R::Response>>;
+
+    fn send_all(&self, tr: &Transmission, limit: usize) -> Self::Responses {
+        let slots: Vec<_> = self.iter().map(|_| Slot::new()).collect();
+        run(self.len(), limit, |i| slots[i].fill(tr.send(&self[i])));
+        slots.into_iter().map(Slot::take).collect()
+    }
+}
+
+impl<R> Batch for Vec<R> where R: Request + Sync, R::Response: Response + Send {
+    type Responses = Vec<Result<R::Response>>;
+
+    fn send_all(&self, tr: &Transmission, limit: usize) -> Self::Responses {
+        self[..].send_all(tr, limit)
+    }
+}
@@ -0,0 +1,31 @@
+//! Runs the fake Transmission daemon.
+//!
+//! Usage: `fake-transmission [ADDRESS] [FIXTURE]`
+//!
+//! The address defaults to `127.0.0.1:9091`. The credentials can be set using
+//! the `TR_AUTH` environment variable in the form `user:password`.
+
+extern crate transmission_rpc;
+
+use std::env;
+use transmission_rpc::fake::FakeDaemon;
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let addr = args.next().unwrap_or("127.0.0.1:9091".to_string());
+
+    let mut daemon = FakeDaemon::new();
+
+    if let Some(fixture) = args.next() {
+        daemon = daemon.fixture(&fixture).expect("Failed to read the fixture.");
+    }
+
+    if let Ok(auth) = env::var("TR_AUTH") {
+        let mut auth = auth.splitn(2, ':');
+        daemon = daemon.auth(auth.next().unwrap(), auth.next().unwrap_or(""));
+    }
+
+    let server = daemon.start(&*addr).expect("Failed to start the daemon.");
+    println!("Listening on {}", server.url());
+    server.join();
+}
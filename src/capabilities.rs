@@ -0,0 +1,48 @@
+use responses::GetSession;
+
+/// The newest version of the RPC interface this library knows how to talk to.
+pub const RPC_VERSION: u64 = 18;
+
+/// Describes what the daemon the client is connected to is capable of.
+/// Obtained by calling `Transmission::negotiate`.
+#[derive(Debug, Clone)]
+pub struct Capabilities {
+    /// The current version of the daemon's RPC interface.
+    pub rpc_version: u64,
+    /// The oldest version of the RPC interface the daemon is compatible with.
+    pub rpc_version_minimum: u64,
+    /// The version of the daemon.
+    pub version: String
+}
+
+impl Capabilities {
+    /// Returns true if the daemon supports requests introduced in the given RPC version.
+    pub fn supports(&self, rpc_version: u64) -> bool {
+        rpc_version <= self.rpc_version
+    }
+
+    /// Returns true if the daemon still accepts requests made by this library.
+    pub fn is_compatible(&self) -> bool {
+        self.rpc_version_minimum <= RPC_VERSION
+    }
+
+    /// Returns true if the daemon supports the `table` format in `torrent-get`.
+    pub fn supports_table_format(&self) -> bool {
+        self.supports(16)
+    }
+
+    /// Returns true if the daemon supports the `torrent-reannounce` method.
+    pub fn supports_reannounce(&self) -> bool {
+        self.supports(5)
+    }
+}
+
+impl From<GetSession> for Capabilities {
+    fn from(session: GetSession) -> Capabilities {
+        Capabilities {
+            rpc_version: session.rpc_version,
+            rpc_version_minimum: session.rpc_version_minimum,
+            version: session.version
+        }
+    }
+}
@@ -1,198 +1,568 @@
+//! The errors returned by the library.
+//!
+//! `Error` is divided into the kinds of failures: delivering the request (`transport`),
+//! agreeing on how to talk to the daemon (`protocol`), errors reported by the daemon
+//! (`daemon`) and interpreting the daemon's response (`deserialize`). Errors returned by
+//! `Transmission::send` are wrapped in `Error::Call`, which tells which request failed.
+
+use hyper;
+use openssl::ssl::error::SslError;
+use serde_json;
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+
+pub type Result<T> = ::std::result::Result<T, Error>;
+
+/// The maximum number of bytes of the daemon's response kept in `Context`.
+const MAX_BODY_LEN: usize = 512;
+
+/// A general error type used troughout the library.
+#[derive(Debug)]
+pub enum Error {
+    /// Delivering the request to the daemon failed
+    Transport(transport::Error),
+    /// The client and the daemon could not agree on how to communicate
+    Protocol(protocol::Error),
+    /// Daemon returned an error
+    Daemon(daemon::Error),
+    /// Deserializing the response from the daemon failed
+    Deserialize(deserialize::Error),
+    /// General IO error
+    Io(io::Error),
+    /// The request was not sent because of a dry run. Contains the RPC method.
+    /// See `interceptor::DryRun`.
+    DryRun(String),
+    /// A request sent to the daemon failed. Contains the request's context and the error.
+    Call(Context, Box<Error>)
+}
+
+/// Information about the request that failed.
+#[derive(Debug, Clone)]
+pub struct Context {
+    /// The RPC method of the request
+    pub method: String,
+    /// The tag of the request
+    pub tag: usize,
+    /// The beginning of the daemon's response, if one was received
+    pub body: Option<String>
+}
+
+impl Context {
+    /// Creates the context of a request. At most 512 bytes of the response are kept.
+    pub fn new(method: &str, tag: usize, body: Option<&[u8]>) -> Context {
+        Context {
+            method: method.to_string(),
+            tag: tag,
+            body: body.map(|body| {
+                let mut text = String::from_utf8_lossy(body).into_owned();
+                if text.len() > MAX_BODY_LEN {
+                    let mut end = MAX_BODY_LEN;
+                    while !text.is_char_boundary(end) {
+                        end -= 1;
+                    }
+                    text.truncate(end);
+                    text.push_str("...");
+                }
+                text
+            })
+        }
+    }
+}
+
+impl Error {
+    /// Wraps the error in the context of the given request unless it already has one.
+    pub fn in_call(self, method: &str, tag: usize, body: Option<&[u8]>) -> Error {
+        match self {
+            Error::Call(context, error) => Error::Call(context, error),
+            error => Error::Call(Context::new(method, tag, body), Box::new(error))
+        }
+    }
+
+    /// Returns the context of the request that failed.
+    pub fn context(&self) -> Option<&Context> {
+        match *self {
+            Error::Call(ref context, _) => Some(context),
+            _ => None
+        }
+    }
+
+    /// Returns the tag of the request that failed.
+    pub fn tag(&self) -> Option<usize> {
+        self.context().map(|context| context.tag)
+    }
+
+    /// Returns true if the request was skipped by a dry run.
+    pub fn is_dry_run(&self) -> bool {
+        match *self.root() {
+            Error::DryRun(_) => true,
+            _ => false
+        }
+    }
+
+    /// Returns the error without the context of the request.
+    pub fn root(&self) -> &Error {
+        match *self {
+            Error::Call(_, ref error) => error.root(),
+            ref error => error
+        }
+    }
+}
+
+impl StdError for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::Transport(_) => "transport error",
+            Error::Protocol(_) => "protocol error",
+            Error::Daemon(_) => "daemon returned an error",
+            Error::Deserialize(_) => "deserializing error",
+            Error::Io(_) => "IO error",
+            Error::DryRun(_) => "request skipped in a dry run",
+            Error::Call(_, _) => "request failed"
+        }
+    }
+
+    fn source(&self) -> Option<&(StdError + 'static)> {
+        match *self {
+            Error::Transport(ref e) => Some(e),
+            Error::Protocol(ref e) => Some(e),
+            Error::Daemon(ref e) => Some(e),
+            Error::Deserialize(ref e) => Some(e),
+            Error::Io(ref e) => Some(e),
+            Error::DryRun(_) => None,
+            Error::Call(_, ref e) => Some(&**e)
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Transport(ref e) => write!(f, "transport error: {}", e),
+            Error::Protocol(ref e) => write!(f, "protocol error: {}", e),
+            Error::Daemon(ref e) => write!(f, "daemon returned an error: {}", e),
+            Error::Deserialize(ref e) => write!(f, "deserializing error: {}", e),
+            Error::Io(ref e) => write!(f, "IO error: {}", e),
+            Error::DryRun(ref method) => write!(f, "'{}' request skipped in a dry run", method),
+            Error::Call(ref context, ref e) => match context.body {
+                Some(ref body) => write!(f, "'{}' request with tag {} failed: {} (response: {})",
+                                         context.method, context.tag, e, body),
+                None => write!(f, "'{}' request with tag {} failed: {}",
+                               context.method, context.tag, e)
+            }
+        }
+    }
+}
+
+impl From<transport::Error> for Error {
+    fn from(e: transport::Error) -> Error {
+        Error::Transport(e)
+    }
+}
+
+impl From<protocol::Error> for Error {
+    fn from(e: protocol::Error) -> Error {
+        Error::Protocol(e)
+    }
+}
+
+impl From<daemon::Error> for Error {
+    fn from(e: daemon::Error) -> Error {
+        Error::Daemon(e)
+    }
+}
+
+impl From<deserialize::Error> for Error {
+    fn from(e: deserialize::Error) -> Error {
+        Error::Deserialize(e)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+impl From<hyper::Error> for Error {
+    fn from(e: hyper::Error) -> Error {
+        Error::Transport(transport::Error::Http(e))
+    }
+}
+
+impl From<SslError> for Error {
+    fn from(e: SslError) -> Error {
+        Error::Transport(transport::Error::Tls(e))
+    }
+}
+
+#[cfg(feature = "async")]
+impl From<::reqwest::Error> for Error {
+    fn from(e: ::reqwest::Error) -> Error {
+        Error::Transport(transport::Error::Reqwest(e))
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Error {
+        Error::Deserialize(deserialize::Error::Json(e))
+    }
+}
+
+pub mod transport {
+    use hyper;
+    use openssl::ssl::error::SslError;
+    use std::error::Error as StdError;
+    use std::fmt;
+
+    /// An error returned when delivering the request to the daemon fails.
+    #[derive(Debug)]
+    pub enum Error {
+        /// Contacting the daemon over HTTP failed
+        Http(hyper::Error),
+        /// Setting up TLS failed
+        Tls(SslError),
+        /// Contacting the daemon using the asynchronous client failed
+        #[cfg(feature = "async")]
+        Reqwest(::reqwest::Error),
+        /// The daemon did not respond in time
+        Timeout,
+        /// The proxy url could not be parsed. The payload is the url.
+        InvalidProxy(String),
+        /// The certificate pin is not a SHA-256 fingerprint. The payload is the pin.
+        InvalidPin(String),
+        /// No recorded response matches the request. The payload is the request's method.
+        NotRecorded(String)
+    }
+
+    impl StdError for Error {
+        fn description(&self) -> &str {
+            match *self {
+                Error::Http(_) => "HTTP error",
+                Error::Tls(_) => "TLS error",
+                #[cfg(feature = "async")]
+                Error::Reqwest(_) => "HTTP error",
+                Error::Timeout => "daemon did not respond in time",
+                Error::InvalidProxy(_) => "invalid proxy url",
+                Error::InvalidPin(_) => "invalid certificate pin",
+                Error::NotRecorded(_) => "no recorded response matches the request"
+            }
+        }
+
+        fn source(&self) -> Option<&(StdError + 'static)> {
+            match *self {
+                Error::Http(ref e) => Some(e),
+                Error::Tls(ref e) => Some(e),
+                #[cfg(feature = "async")]
+                Error::Reqwest(ref e) => Some(e),
+                _ => None
+            }
+        }
+    }
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match *self {
+                Error::Http(ref e) => write!(f, "HTTP error: {}", e),
+                Error::Tls(ref e) => write!(f, "TLS error: {}", e),
+                #[cfg(feature = "async")]
+                Error::Reqwest(ref e) => write!(f, "HTTP error: {}", e),
+                Error::Timeout => write!(f, "daemon did not respond in time"),
+                Error::InvalidProxy(ref url) => write!(f, "'{}' is not a valid proxy url", url),
+                Error::InvalidPin(ref pin) => write!(f, "'{}' is not a SHA-256 fingerprint", pin),
+                Error::NotRecorded(ref method) =>
+                    write!(f, "no recorded response matches the '{}' request", method)
+            }
+        }
+    }
+}
+
+pub mod protocol {
+    use std::error::Error as StdError;
+    use std::fmt;
+
+    /// An error returned when the client and the daemon can't agree on how to communicate.
+    #[derive(Debug)]
+    pub enum Error {
+        /// The request requires a newer RPC version than the daemon supports.
+        Unsupported { method: String, required: u64, available: u64 },
+        /// The daemon no longer supports the RPC version used by this library.
+        /// The payload is the oldest version the daemon supports.
+        Incompatible(u64),
+        /// The response belongs to a different request. `received` is `None` if the
+        /// response has no numeric tag.
+        TagMismatch { expected: usize, received: Option<usize> },
+        /// The daemon responded with 409 Conflict without a valid session id.
+        MissingSessionId,
+        /// The daemon kept rejecting the session id. The payload is the number of renegotiations.
+        SessionConflict(usize)
+    }
+
+    impl StdError for Error {
+        fn description(&self) -> &str {
+            match *self {
+                Error::Unsupported { .. } => "request is not supported by the daemon",
+                Error::Incompatible(_) => "daemon is incompatible with this library",
+                Error::TagMismatch { .. } => "response does not belong to the request",
+                Error::MissingSessionId => "daemon did not send a valid session id",
+                Error::SessionConflict(_) => "daemon kept rejecting the session id"
+            }
+        }
+    }
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match *self {
+                Error::Unsupported { ref method, required, available } =>
+                    write!(f, "'{}' requires RPC version {} but it is unsupported by daemon RPC v{}",
+                           method, required, available),
+                Error::Incompatible(minimum) =>
+                    write!(f, "daemon requires at least RPC version {} but this library supports only version {}",
+                           minimum, ::capabilities::RPC_VERSION),
+                Error::TagMismatch { expected, received: Some(received) } =>
+                    write!(f, "expected a response with tag {} but received tag {}", expected, received),
+                Error::TagMismatch { expected, received: None } =>
+                    write!(f, "expected a response with tag {} but received no tag", expected),
+                Error::MissingSessionId =>
+                    write!(f, "daemon responded with 409 Conflict without a valid session id"),
+                Error::SessionConflict(attempts) =>
+                    write!(f, "daemon rejected the session id after {} renegotiations", attempts)
+            }
+        }
+    }
+}
+
 pub mod deserialize {
-    error_chain! {
-        foreign_links {
-            Json(::serde_json::Error);
+    use serde_json;
+    use std::error::Error as StdError;
+    use std::fmt;
+
+    pub type Result<T> = ::std::result::Result<T, Error>;
+
+    /// An error type returned when deserializing the deamon's response fails.
+    #[derive(Debug)]
+    pub enum Error {
+        /// JSON related error
+        Json(serde_json::Error),
+        /// The response is missing an expected field. The payload is the fields name.
+        MissingField(String),
+        /// A field on the response has an invalid type. The first field is
+        /// the name of the expected type and the second is the field's name.
+        InvalidType(String, String)
+    }
+
+    impl StdError for Error {
+        fn description(&self) -> &str {
+            match *self {
+                Error::Json(_) => "JSON error",
+                Error::MissingField(_) => "recieved JSON object is missing a required field",
+                Error::InvalidType(_, _) => "received JSON object had a field with an invalid type"
+            }
         }
 
-        errors {
-            MissingField(field: String) {
-                description("recieved JSON object is missing a required field")
-                display("received JSON object is missing field '{}'", field)
+        fn source(&self) -> Option<&(StdError + 'static)> {
+            match *self {
+                Error::Json(ref e) => Some(e),
+                _ => None
             }
-            InvalidType(field: String, expected: String) {
-                description("received JSON object had a field with an invalid type")
-                display("expected value of type '{}' in field '{}'", expected, field)
+        }
+    }
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match *self {
+                Error::Json(ref e) => write!(f, "JSON error: {}", e),
+                Error::MissingField(ref field) =>
+                    write!(f, "received JSON object is missing field '{}'", field),
+                Error::InvalidType(ref expected, ref field) =>
+                    write!(f, "expected value of type '{}' in field '{}'", expected, field)
             }
         }
     }
+
+    impl From<serde_json::Error> for Error {
+        fn from(e: serde_json::Error) -> Error {
+            Error::Json(e)
+        }
+    }
 }
 
 pub mod daemon {
-    error_chain! {
-        errors {
-            StatusCode(s: ::hyper::status::StatusCode) {
-                description("deamon returned an unsuccessfull status code")
-                display("deamon returned status code: {}", s)
+    use hyper::status::StatusCode;
+    use std::error::Error as StdError;
+    use std::fmt;
+
+    /// An error type returned when daemon returns an error
+    #[derive(Debug)]
+    pub enum Error {
+        /// The response from the daemon had a HTTP status code different from 200
+        StatusCode(StatusCode),
+        /// The daemon rejected the credentials (401). The payload is the daemon's explanation.
+        Unauthorized(String),
+        /// The client's IP address is not whitelisted (403). The payload is the daemon's explanation.
+        IpWhitelist(String),
+        /// The daemon's host name is not whitelisted (421). The payload is the daemon's explanation.
+        HostWhitelist(String),
+        /// The daemon responded with an error message
+        Message(MessageKind, String),
+        /// The daemon responded with a JSON-RPC error. The message is classified like the
+        /// legacy error messages.
+        JsonRpc(JsonRpcCode, MessageKind, String)
+    }
+
+    impl Error {
+        /// Returns the kind of the error message for the errors that have one, regardless
+        /// of the protocol used to talk to the daemon.
+        pub fn message_kind(&self) -> Option<MessageKind> {
+            match *self {
+                Error::Message(kind, _) | Error::JsonRpc(_, kind, _) => Some(kind),
+                _ => None
             }
-            
-            Message(msg: String) {
-                description("daemon returned an error message")
-                display("daemon error: {}", msg)
+        }
+    }
+
+    impl StdError for Error {
+        fn description(&self) -> &str {
+            match *self {
+                Error::StatusCode(_) => "deamon returned an unsuccessfull status code",
+                Error::Unauthorized(_) => "daemon rejected the credentials",
+                Error::IpWhitelist(_) => "client's IP address is not whitelisted",
+                Error::HostWhitelist(_) => "daemon's host name is not whitelisted",
+                Error::Message(_, _) => "daemon returned an error message",
+                Error::JsonRpc(_, _, _) => "daemon returned a JSON-RPC error"
             }
         }
     }
-}
 
-error_chain! {
-    links {
-        Deserialize(deserialize::Error, deserialize::ErrorKind);
-        Daemon(daemon::Error, daemon::ErrorKind);
-    }
-    
-    foreign_links {
-        Http(::hyper::Error);
-        Io(::std::io::Error);
-    }
-}
-
-// pub type Result<T> = ::std::result::Result<T, Error>;
-
-
-// /// A general error type used troughout the library.
-// #[derive(Debug)]
-// pub enum Error {
-//     /// Contacting the daemon failed
-//     Http(hyper::Error),
-//     /// Deserializing the response from the daemon failed
-//     Deserialize(DeserializeError),
-//     /// Daemon returned an error
-//     Daemon(DaemonError),
-//     /// General IO error
-//     Io(io::Error)
-// }
-
-// /// An error type returned when deserializing the deamon's response fails.
-// #[derive(Debug)]
-// pub enum DeserializeError {
-//     /// JSON related error
-//     Json(serde_json::Error),
-//     /// The response is missing an expected field. The payload is the fields name.
-//     MissingField(String),
-//     /// A field on the response has an invalid type. The first field is
-//     /// the name of the expected type and the second is the field's name. 
-//     InvalidType(String, String),
-// }
-
-// /// An error type returned when daemon returns an error
-// #[derive(Debug)]
-// pub enum DaemonError {
-//     /// The response from the daemon had a HTTP status code different from 200
-//     StatusCode(StatusCode),
-//     /// The daemon responded with an error message
-//     Result(String)
-// }
-
-// impl StdError for Error {
-//     fn description(&self) -> &str {
-//         match self {
-//             &Error::Http(_) => "HTTP error",
-//             &Error::Deserialize(_) => "deserializing error",
-//             &Error::Daemon(_) => "daemon returned an error",
-//             &Error::Io(_) => "IO error"
-//         }
-//     }
-    
-//     fn cause(&self) -> Option<&StdError> {
-//         match self {
-//             &Error::Http(ref e) => Some(e),
-//             &Error::Deserialize(ref e) => Some(e),
-//             &Error::Daemon(ref e) => Some(e),
-//             &Error::Io(ref e) => Some(e)
-//         }
-//     }
-// }
-
-// impl fmt::Display for Error {
-//     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-//         match self {
-//             &Error::Http(ref e) =>
-//                 write!(f, "HTTP error: {}", e),
-//             &Error::Deserialize(ref e) =>
-//                 write!(f, "deserializing error: {}", e),
-//             &Error::Daemon(ref e) =>
-//                 write!(f, "daemon returned an error: {}", e),
-//             &Error::Io(ref e) =>
-//                 write!(f, "IO error: {}", e)
-//         }
-//     }
-// }
-
-// impl StdError for DeserializeError {
-//     fn description(&self) -> &str {
-//         match self {
-//             &DeserializeError::Json(ref e) => "JSON error",
-//             &DeserializeError::MissingField(_) => "Missing field",
-//             &DeserializeError::InvalidType(_, _) => "Invalid type"
-//         }
-//     }
-
-//     fn cause(&self) -> Option<&StdError> {
-//         match self {
-//             &DeserializeError::Json(ref e) => Some(e),
-//             _ => None
-//         }
-//     }
-// }
-
-// impl fmt::Display for DeserializeError {
-//     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-//         match self {
-//             &DeserializeError::Json(ref e) =>
-//                 write!(f, "JSON Error: {}", e),
-//             &DeserializeError::MissingField(ref name) =>
-//                 write!(f, "Missing field '{}'", name),
-//             &DeserializeError::InvalidType(ref typ, ref name) =>
-//                 write!(f, "Field type '{}' for field '{}'", typ, name)
-//         }
-//     }
-// }
-
-// impl StdError for DaemonError {
-//     fn description(&self) -> &str {
-//         match self {
-//             &DaemonError::StatusCode(_) => "non-success HTTP status code",
-//             &DaemonError::Result(_) => "daemon returned an error"
-//         }
-//     }
-// }
-
-// impl fmt::Display for DaemonError {
-//     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-//         match self {
-//             &DaemonError::StatusCode(ref c) =>
-//                 write!(f, "non-success HTTP status code: {}", c),
-//             &DaemonError::Result(ref e) =>
-//                 write!(f, "daemon returned an error: {}", e)
-//         }
-//     }
-// }
-
-// impl From<DeserializeError> for Error {
-//     fn from(e: DeserializeError) -> Error {
-//         Error::Deserialize(e)
-//     }
-// }
-
-// impl From<serde_json::Error> for Error {
-//     fn from(e: serde_json::Error) -> Error {
-//         Error::Deserialize(DeserializeError::Json(e))
-//     }
-// }
-
-// impl From<hyper::Error> for Error {
-//     fn from(e: hyper::Error) -> Error {
-//         Error::Http(e)
-//     }
-// }
-
-// impl From<io::Error> for Error {
-//     fn from(e: io::Error) -> Error {
-//         Error::Io(e)
-//     }
-// }
-
-// impl From<serde_json::Error> for DeserializeError {
-//     fn from(e: serde_json::Error) -> DeserializeError {
-//         DeserializeError::Json(e)
-//     }
-// }
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match *self {
+                Error::StatusCode(s) => write!(f, "deamon returned status code: {}", s),
+                Error::Unauthorized(ref explanation) =>
+                    write!(f, "daemon rejected the credentials, check 'rpc-username' and 'rpc-password': {}",
+                           explanation),
+                Error::IpWhitelist(ref explanation) =>
+                    write!(f, "client's IP address is not whitelisted, check 'rpc-whitelist': {}",
+                           explanation),
+                Error::HostWhitelist(ref explanation) =>
+                    write!(f, "daemon's host name is not whitelisted, check 'rpc-host-whitelist': {}",
+                           explanation),
+                Error::Message(_, ref msg) => write!(f, "daemon error: {}", msg),
+                Error::JsonRpc(code, _, ref msg) => write!(f, "daemon error ({:?}): {}", code, msg)
+            }
+        }
+    }
+
+    /// The known kinds of error messages returned by the daemon in the `result` field.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum MessageKind {
+        /// The metainfo given to `torrent-add` could not be parsed.
+        InvalidTorrent,
+        /// The torrent has already been added.
+        DuplicateTorrent,
+        /// A file or a directory given to the daemon does not exist.
+        NoSuchFile,
+        /// The daemon failed to fetch the metainfo from the url given to `torrent-add`.
+        MetadataFetch,
+        /// An argument of the request was invalid.
+        InvalidArgument,
+        /// The daemon does not know the method of the request.
+        UnrecognizedMethod,
+        /// Any other message.
+        Other
+    }
+
+    impl MessageKind {
+        /// Classifies an error message returned by the daemon.
+        pub fn classify(msg: &str) -> MessageKind {
+            let msg = msg.to_lowercase();
+
+            if msg.contains("invalid or corrupt torrent file") {
+                MessageKind::InvalidTorrent
+            } else if msg.contains("duplicate torrent") {
+                MessageKind::DuplicateTorrent
+            } else if msg.contains("no such file or directory") {
+                MessageKind::NoSuchFile
+            } else if msg.contains("gotmetadatafromurl") {
+                MessageKind::MetadataFetch
+            } else if msg.contains("invalid argument") {
+                MessageKind::InvalidArgument
+            } else if msg.contains("method name not recognized") || msg.contains("no method name") {
+                MessageKind::UnrecognizedMethod
+            } else {
+                MessageKind::Other
+            }
+        }
+    }
+
+    /// Returns the error for an unsuccessful HTTP response.
+    pub fn from_status(status: StatusCode, body: &[u8]) -> Error {
+        match status.to_u16() {
+            401 => Error::Unauthorized(explanation(body)),
+            403 => Error::IpWhitelist(explanation(body)),
+            421 => Error::HostWhitelist(explanation(body)),
+            _ => Error::StatusCode(status)
+        }
+    }
+
+    /// Extracts the explanation from the HTML page the daemon sends along with an
+    /// unsuccessful status code. The title and the heading, which only repeat the
+    /// status code, are left out.
+    fn explanation(body: &[u8]) -> String {
+        let html = String::from_utf8_lossy(body);
+        let mut text = String::with_capacity(html.len());
+        let mut rest = &*html;
+
+        while let Some(start) = rest.find('<') {
+            text.push_str(&rest[..start]);
+            text.push(' ');
+            rest = &rest[start..];
+
+            let skip_until = if rest.starts_with("<title>") {
+                "</title>"
+            } else if rest.starts_with("<h1>") {
+                "</h1>"
+            } else {
+                ">"
+            };
+
+            rest = match rest.find(skip_until) {
+                Some(end) => &rest[end + skip_until.len()..],
+                None => ""
+            };
+        }
+        text.push_str(rest);
+
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    /// The code of an error returned by the daemon using the JSON-RPC 2.0 protocol.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum JsonRpcCode {
+        /// The daemon could not parse the request.
+        ParseError,
+        /// The request was not a valid JSON-RPC request.
+        InvalidRequest,
+        /// The daemon does not recognize the method.
+        MethodNotFound,
+        /// The arguments of the request were invalid.
+        InvalidParams,
+        /// The daemon encountered an internal error.
+        InternalError,
+        /// An application specific error code.
+        Other(i64)
+    }
+
+    impl JsonRpcCode {
+        pub fn from_code(code: i64) -> JsonRpcCode {
+            match code {
+                -32700 => JsonRpcCode::ParseError,
+                -32600 => JsonRpcCode::InvalidRequest,
+                -32601 => JsonRpcCode::MethodNotFound,
+                -32602 => JsonRpcCode::InvalidParams,
+                -32603 => JsonRpcCode::InternalError,
+                code => JsonRpcCode::Other(code)
+            }
+        }
+    }
+}
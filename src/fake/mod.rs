@@ -0,0 +1,215 @@
+//! A fake Transmission daemon for testing without a real daemon.
+//!
+//! The fake daemon speaks the legacy RPC protocol over HTTP, including the session id
+//! handshake and Basic authentication, and keeps the torrents in memory. It implements
+//! `session-get`, `torrent-get`, `torrent-add`, `torrent-set`, `torrent-remove` and the
+//! torrent actions.
+//!
+//! Available with the `fake-daemon` feature.
+//!
+//! ```no_run
+//! use transmission_rpc::Transmission;
+//! use transmission_rpc::fake::FakeDaemon;
+//!
+//! let daemon = FakeDaemon::new()
+//!     .auth("user", "password")
+//!     .fixture("torrents.json").unwrap()
+//!     .start("127.0.0.1:0").unwrap();
+//!
+//! let mut tr = Transmission::new();
+//! tr.set_url(daemon.url());
+//! tr.set_auth("user".to_string(), "password".to_string());
+//! ```
+
+mod state;
+
+use self::state::{State, Object};
+use error::*;
+use hyper::Url;
+use hyper::header::{Authorization, Basic};
+use hyper::net::Fresh;
+use hyper::server::{self, Handler, Listening, Server};
+use hyper::status::StatusCode;
+use serde_json::{self, Value};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::mem;
+use std::net::ToSocketAddrs;
+use std::path::Path;
+use std::sync::Mutex;
+use SESSION_HEADER;
+
+/// Builder for the fake daemon.
+pub struct FakeDaemon {
+    auth: Option<(String, String)>,
+    torrents: Vec<Value>
+}
+
+impl FakeDaemon {
+    /// Creates a fake daemon without any torrents or authentication.
+    pub fn new() -> FakeDaemon {
+        FakeDaemon {
+            auth: None,
+            torrents: Vec::new()
+        }
+    }
+
+    /// Requires the clients to authenticate using the given credentials.
+    pub fn auth<S: Into<String>>(mut self, username: S, password: S) -> Self {
+        self.auth = Some((username.into(), password.into()));
+        self
+    }
+
+    /// Adds the torrents to the daemon. The torrents are objects in the format returned
+    /// by `torrent-get`, e.g. `{"id": 1, "name": "ubuntu.iso", "status": 6}`.
+    pub fn torrents(mut self, torrents: Vec<Value>) -> Self {
+        self.torrents.extend(torrents);
+        self
+    }
+
+    /// Adds the torrents from a fixture file. The file contains a JSON object
+    /// like the arguments of a `torrent-get` response: `{"torrents": [...]}`.
+    pub fn fixture<P: AsRef<Path>>(self, path: P) -> Result<Self> {
+        let mut json = String::new();
+        try!(File::open(path)).read_to_string(&mut json)?;
+        self.fixture_str(&json)
+    }
+
+    /// Adds the torrents from a fixture. See `fixture`.
+    pub fn fixture_str(self, json: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(json)?;
+
+        match value.find("torrents").and_then(|t| t.as_array()) {
+            Some(torrents) => Ok(self.torrents(torrents.clone())),
+            None => Err(deserialize::Error::MissingField("torrents".to_string()).into())
+        }
+    }
+
+    /// Starts the daemon on the given address. Use port 0 to pick a free port.
+    pub fn start<A: ToSocketAddrs>(self, addr: A) -> Result<FakeServer> {
+        let handler = FakeHandler {
+            auth: self.auth,
+            session: format!("fake-session-{}", ::std::process::id()),
+            state: Mutex::new(State::new(self.torrents))
+        };
+
+        let listening = try!(try!(Server::http(addr)).handle(handler));
+        let url = Url::parse(&format!("http://{}/transmission/rpc", listening.socket)).unwrap();
+
+        Ok(FakeServer {
+            listening: Some(listening),
+            url: url
+        })
+    }
+}
+
+/// A running fake daemon. The daemon keeps running until the process exits.
+pub struct FakeServer {
+    listening: Option<Listening>,
+    url: Url
+}
+
+impl FakeServer {
+    /// The url of the daemon's RPC interface.
+    pub fn url(&self) -> Url {
+        self.url.clone()
+    }
+
+    /// Blocks the current thread for as long as the daemon is running.
+    pub fn join(mut self) {
+        if let Some(listening) = self.listening.take() {
+            drop(listening);
+        }
+    }
+}
+
+impl Drop for FakeServer {
+    fn drop(&mut self) {
+        // Dropping `Listening` joins the server thread, which never ends.
+        if let Some(listening) = self.listening.take() {
+            mem::forget(listening);
+        }
+    }
+}
+
+struct FakeHandler {
+    auth: Option<(String, String)>,
+    session: String,
+    state: Mutex<State>
+}
+
+impl FakeHandler {
+    fn is_authorized(&self, req: &server::Request) -> bool {
+        match self.auth {
+            None => true,
+            Some((ref username, ref password)) => match req.headers.get::<Authorization<Basic>>() {
+                Some(&Authorization(ref basic)) =>
+                    &basic.username == username && basic.password.as_ref() == Some(password),
+                None => false
+            }
+        }
+    }
+
+    fn has_session(&self, req: &server::Request) -> bool {
+        req.headers.get_raw(SESSION_HEADER)
+            .and_then(|v| v.get(0))
+            .map(|v| &v[..] == self.session.as_bytes())
+            .unwrap_or(false)
+    }
+
+    /// Handles the JSON request and returns the JSON response.
+    fn call(&self, body: &[u8]) -> Value {
+        let request: Value = serde_json::from_slice(body).unwrap_or(Value::Null);
+
+        let method = request.find("method").and_then(|m| m.as_str()).unwrap_or("");
+        let args = match request.find("arguments") {
+            Some(&Value::Object(ref args)) => args.clone(),
+            _ => Object::new()
+        };
+
+        let (result, arguments) = self.state.lock().unwrap().call(method, &args);
+
+        let mut response = BTreeMap::new();
+        response.insert("result".to_string(), Value::String(result));
+        response.insert("arguments".to_string(), arguments);
+        if let Some(tag) = request.find("tag") {
+            response.insert("tag".to_string(), tag.clone());
+        }
+
+        Value::Object(response)
+    }
+}
+
+impl Handler for FakeHandler {
+    fn handle<'a, 'k>(&'a self, mut req: server::Request<'a, 'k>, mut res: server::Response<'a, Fresh>) {
+        res.headers_mut().set_raw(SESSION_HEADER, vec![self.session.clone().into_bytes()]);
+
+        if !self.is_authorized(&req) {
+            *res.status_mut() = StatusCode::Unauthorized;
+            res.headers_mut().set_raw("WWW-Authenticate", vec![b"Basic realm=\"Transmission\"".to_vec()]);
+            let _ = res.send(b"<html><head><title>401: Unauthorized</title></head>\
+                               <body><h1>401: Unauthorized</h1>Unauthorized User</body></html>");
+            return;
+        }
+
+        if !self.has_session(&req) {
+            *res.status_mut() = StatusCode::Conflict;
+            let body = format!("<html><head><title>409: Conflict</title></head>\
+                                <body><h1>409: Conflict</h1><p>Your request had an invalid session-id header.</p>\
+                                <p><code>{}: {}</code></p></body></html>", SESSION_HEADER, self.session);
+            let _ = res.send(body.as_bytes());
+            return;
+        }
+
+        let mut body = Vec::new();
+        if req.read_to_end(&mut body).is_err() {
+            *res.status_mut() = StatusCode::BadRequest;
+            let _ = res.send(b"");
+            return;
+        }
+
+        let response = serde_json::to_string(&self.call(&body)).unwrap();
+        let _ = res.send(response.as_bytes());
+    }
+}
@@ -0,0 +1,314 @@
+use rustc_serialize::base64::FromBase64;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use requests::RECENTLY_ACTIVE_SECONDS;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub type Object = BTreeMap<String, Value>;
+
+/// The torrents known by the fake daemon and the bookkeeping needed for
+/// `recently-active` requests.
+pub struct State {
+    torrents: BTreeMap<u64, Object>,
+    next_id: u64,
+    /// The ids of the removed torrents and the times they were removed.
+    removed: Vec<(u64, u64)>
+}
+
+/// The result of a method call: the `result` string and the `arguments`.
+pub type Outcome = (String, Value);
+
+fn success(args: Object) -> Outcome {
+    ("success".to_string(), Value::Object(args))
+}
+
+fn failure(msg: &str) -> Outcome {
+    (msg.to_string(), Value::Object(BTreeMap::new()))
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Returns the time from which on torrents count as recently active.
+fn recently_active_since() -> u64 {
+    now().saturating_sub(RECENTLY_ACTIVE_SECONDS)
+}
+
+/// Creates a 40 character hex string from the data, resembling an info hash.
+fn hash_string(data: &str) -> String {
+    (0..3u8).map(|salt| {
+        let mut hasher = DefaultHasher::new();
+        salt.hash(&mut hasher);
+        data.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }).collect::<String>()[..40].to_string()
+}
+
+/// `bandwidth_priority` and `peer-limit` become `bandwidthPriority` and `peerLimit`.
+fn camel_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut upper = false;
+    for ch in name.chars() {
+        if ch == '_' || ch == '-' {
+            upper = true;
+        } else if upper {
+            out.extend(ch.to_uppercase());
+            upper = false;
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
+
+impl State {
+    /// Creates the state from the torrents of a fixture. Torrents without an `id`
+    /// are given one.
+    pub fn new(torrents: Vec<Value>) -> State {
+        let mut state = State {
+            torrents: BTreeMap::new(),
+            next_id: 1,
+            removed: Vec::new()
+        };
+
+        for torrent in torrents {
+            if let Value::Object(mut torrent) = torrent {
+                let id = match torrent.get("id").and_then(|id| id.as_u64()) {
+                    Some(id) => id,
+                    None => state.next_id
+                };
+                torrent.insert("id".to_string(), Value::U64(id));
+                state.next_id = ::std::cmp::max(state.next_id, id + 1);
+                state.torrents.insert(id, torrent);
+            }
+        }
+
+        state
+    }
+
+    /// Calls the method with the given arguments.
+    pub fn call(&mut self, method: &str, args: &Object) -> Outcome {
+        match method {
+            "session-get" => self.session_get(),
+            "torrent-get" => self.torrent_get(args),
+            "torrent-add" => self.torrent_add(args),
+            "torrent-set" => self.torrent_set(args),
+            "torrent-start" | "torrent-start-now" => self.set_status(args, None),
+            "torrent-stop" => self.set_status(args, Some(0)),
+            "torrent-verify" => self.set_status(args, Some(2)),
+            "torrent-reannounce" => self.reannounce(args),
+            "torrent-remove" => self.torrent_remove(args),
+            _ => failure("method name not recognized")
+        }
+    }
+
+    /// Returns the ids of the torrents the `ids` argument refers to.
+    /// A missing `ids` argument refers to all torrents, and `recently-active`
+    /// to the ones whose `activityDate` is within the window.
+    fn select(&self, args: &Object) -> Vec<u64> {
+        let matches = |id: &u64, torrent: &Object, v: &Value| match *v {
+            Value::String(ref hash) => torrent.get("hashString").and_then(|h| h.as_str()) == Some(&**hash),
+            ref v => v.as_u64() == Some(*id)
+        };
+
+        match args.get("ids") {
+            None => self.torrents.keys().cloned().collect(),
+            Some(&Value::String(ref s)) if s == "recently-active" => self.torrents.iter()
+                .filter(|&(_, torrent)| {
+                    let activity = torrent.get("activityDate").and_then(|d| d.as_u64()).unwrap_or(0);
+                    activity >= recently_active_since()
+                })
+                .map(|(id, _)| *id)
+                .collect(),
+            Some(&Value::Array(ref ids)) => self.torrents.iter()
+                .filter(|&(id, torrent)| ids.iter().any(|v| matches(id, torrent, v)))
+                .map(|(id, _)| *id)
+                .collect(),
+            Some(v) => self.torrents.iter()
+                .filter(|&(id, torrent)| matches(id, torrent, v))
+                .map(|(id, _)| *id)
+                .collect()
+        }
+    }
+
+    fn session_get(&self) -> Outcome {
+        let mut args = BTreeMap::new();
+        args.insert("rpc-version".to_string(), Value::U64(17));
+        args.insert("rpc-version-minimum".to_string(), Value::U64(14));
+        args.insert("version".to_string(), Value::String("4.0.0 (fake)".to_string()));
+        success(args)
+    }
+
+    fn torrent_get(&mut self, args: &Object) -> Outcome {
+        let recently_active = args.get("ids").and_then(|ids| ids.as_str()) == Some("recently-active");
+        let ids = self.select(args);
+
+        let fields: Option<Vec<String>> = args.get("fields").and_then(|f| f.as_array()).map(|f| {
+            f.iter().filter_map(|f| f.as_str().map(|s| s.to_string())).collect()
+        });
+
+        let torrents: Vec<Object> = ids.iter().map(|id| {
+            let torrent = &self.torrents[id];
+            match fields {
+                Some(ref fields) => fields.iter()
+                    .filter_map(|f| torrent.get(f).map(|v| (f.clone(), v.clone())))
+                    .collect(),
+                None => torrent.clone()
+            }
+        }).collect();
+
+        let mut result = BTreeMap::new();
+
+        if args.get("format").and_then(|f| f.as_str()) == Some("table") {
+            let keys: Vec<String> = match fields {
+                Some(fields) => fields,
+                None => torrents.iter().flat_map(|t| t.keys().cloned())
+                    .collect::<BTreeSet<_>>().into_iter().collect()
+            };
+
+            let mut rows = vec![Value::Array(keys.iter().map(|k| Value::String(k.clone())).collect())];
+            for torrent in torrents {
+                rows.push(Value::Array(keys.iter()
+                                       .map(|k| torrent.get(k).cloned().unwrap_or(Value::Null))
+                                       .collect()));
+            }
+            result.insert("torrents".to_string(), Value::Array(rows));
+        } else {
+            result.insert("torrents".to_string(),
+                          Value::Array(torrents.into_iter().map(Value::Object).collect()));
+        }
+
+        if recently_active {
+            let since = recently_active_since();
+            self.removed.retain(|&(_, removed)| removed >= since);
+            result.insert("removed".to_string(),
+                          Value::Array(self.removed.iter().map(|&(id, _)| Value::U64(id)).collect()));
+        }
+
+        success(result)
+    }
+
+    fn torrent_add(&mut self, args: &Object) -> Outcome {
+        let source = match (args.get("metainfo"), args.get("filename")) {
+            (Some(&Value::String(ref metainfo)), _) => {
+                // A torrent file is a bencoded dictionary.
+                match metainfo.from_base64() {
+                    Ok(ref bytes) if bytes.first() == Some(&b'd') => metainfo.clone(),
+                    _ => return failure("invalid or corrupt torrent file")
+                }
+            },
+            (_, Some(&Value::String(ref filename))) => filename.clone(),
+            _ => return failure("no filename or metainfo specified")
+        };
+
+        let hash = hash_string(&source);
+        let summary = |torrent: &Object| -> Value {
+            Value::Object(["id", "name", "hashString"].iter()
+                          .map(|k| (k.to_string(), torrent[*k].clone()))
+                          .collect())
+        };
+
+        let mut result = BTreeMap::new();
+
+        let duplicate = self.torrents.values()
+            .find(|t| t.get("hashString").and_then(|h| h.as_str()) == Some(&*hash));
+
+        if let Some(torrent) = duplicate {
+            result.insert("torrent-duplicate".to_string(), summary(torrent));
+            return success(result);
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let name = match args.get("filename").and_then(|f| f.as_str()) {
+            Some(filename) => filename.rsplit('/').next().unwrap_or(filename).to_string(),
+            None => format!("Torrent {}", id)
+        };
+
+        let paused = args.get("paused").and_then(|p| p.as_bool()).unwrap_or(false);
+        let download_dir = args.get("download-dir")
+            .or_else(|| args.get("download_dir"))
+            .cloned()
+            .unwrap_or(Value::String("/var/lib/transmission/downloads".to_string()));
+
+        let mut torrent = BTreeMap::new();
+        torrent.insert("id".to_string(), Value::U64(id));
+        torrent.insert("name".to_string(), Value::String(name));
+        torrent.insert("hashString".to_string(), Value::String(hash));
+        torrent.insert("status".to_string(), Value::U64(if paused { 0 } else { 4 }));
+        torrent.insert("addedDate".to_string(), Value::U64(now()));
+        torrent.insert("activityDate".to_string(), Value::U64(now()));
+        torrent.insert("downloadDir".to_string(), download_dir);
+        torrent.insert("percentDone".to_string(), Value::F64(0.0));
+        torrent.insert("error".to_string(), Value::U64(0));
+        torrent.insert("errorString".to_string(), Value::String(String::new()));
+        torrent.insert("labels".to_string(), Value::Array(Vec::new()));
+
+        result.insert("torrent-added".to_string(), summary(&torrent));
+        self.torrents.insert(id, torrent);
+
+        success(result)
+    }
+
+    fn torrent_set(&mut self, args: &Object) -> Outcome {
+        for id in self.select(args) {
+            let torrent = self.torrents.get_mut(&id).unwrap();
+
+            for (key, value) in args.iter().filter(|&(k, _)| k != "ids") {
+                let key = match &**key {
+                    "location" => "downloadDir".to_string(),
+                    key => camel_case(key)
+                };
+                torrent.insert(key, value.clone());
+            }
+
+            torrent.insert("activityDate".to_string(), Value::U64(now()));
+        }
+
+        success(BTreeMap::new())
+    }
+
+    /// Starts, stops or verifies the selected torrents. Starting a torrent
+    /// puts it in seeding or downloading state depending on its progress.
+    fn set_status(&mut self, args: &Object, status: Option<u64>) -> Outcome {
+        for id in self.select(args) {
+            let torrent = self.torrents.get_mut(&id).unwrap();
+
+            let status = status.unwrap_or_else(|| {
+                match torrent.get("percentDone").and_then(|p| p.as_f64()) {
+                    Some(done) if done >= 1.0 => 6,
+                    _ => 4
+                }
+            });
+
+            torrent.insert("status".to_string(), Value::U64(status));
+            torrent.insert("activityDate".to_string(), Value::U64(now()));
+        }
+
+        success(BTreeMap::new())
+    }
+
+    /// Reannounces the selected torrents, which makes them recently active
+    /// but leaves their status alone.
+    fn reannounce(&mut self, args: &Object) -> Outcome {
+        for id in self.select(args) {
+            let torrent = self.torrents.get_mut(&id).unwrap();
+            torrent.insert("activityDate".to_string(), Value::U64(now()));
+        }
+
+        success(BTreeMap::new())
+    }
+
+    fn torrent_remove(&mut self, args: &Object) -> Outcome {
+        for id in self.select(args) {
+            self.torrents.remove(&id);
+            self.removed.push((id, now()));
+        }
+
+        success(BTreeMap::new())
+    }
+}
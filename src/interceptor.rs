@@ -0,0 +1,125 @@
+//! Interceptors that are run around every request sent by `Transmission`.
+//!
+//! An interceptor sees the request as it is sent to the daemon and the arguments of the
+//! response, or the error, after the response has been deserialized. Interceptors are run in the order
+//! they were added before the request is sent, and in the reverse order after it.
+
+use error::{Error, Result};
+use protocol::Protocol;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use trace::redact_arguments;
+
+/// Information about the request passed to the interceptors.
+#[derive(Debug, Clone, Copy)]
+pub struct CallInfo<'a> {
+    /// The RPC method of the request, e.g. `torrent-get`
+    pub method: &'a str,
+    /// The tag of the request
+    pub tag: usize,
+    /// The protocol the request is encoded with
+    pub protocol: Protocol
+}
+
+/// A middleware that is run around every request.
+pub trait Interceptor: Send + Sync {
+    /// Called with the encoded request before it is sent. The request may be modified.
+    /// Returning a result skips sending the request and the interceptors after this one,
+    /// and the result is used as the arguments of the response instead.
+    fn before(&self, _info: &CallInfo, _request: &mut Value) -> Option<Result<Value>> {
+        None
+    }
+
+    /// Called after the request with the arguments of the response once they have been
+    /// deserialized, or with the error if sending the request or deserializing the
+    /// response failed.
+    fn after(&self, _info: &CallInfo, _result: ::std::result::Result<&Value, &Error>) {}
+}
+
+/// Writes a line for every request and response. Passwords and cookies are redacted.
+pub struct Logger<W: Write + Send> {
+    out: Mutex<W>
+}
+
+impl<W: Write + Send> Logger<W> {
+    pub fn new(out: W) -> Logger<W> {
+        Logger { out: Mutex::new(out) }
+    }
+}
+
+impl Logger<io::Stderr> {
+    /// Creates a logger that writes to the standard error.
+    pub fn stderr() -> Logger<io::Stderr> {
+        Logger::new(io::stderr())
+    }
+}
+
+impl<W: Write + Send> Interceptor for Logger<W> {
+    fn before(&self, info: &CallInfo, request: &mut Value) -> Option<Result<Value>> {
+        let mut out = self.out.lock().unwrap();
+        let _ = writeln!(out, "-> {} [{}] {}", info.method, info.tag, redact_arguments(request.clone()));
+        None
+    }
+
+    fn after(&self, info: &CallInfo, result: ::std::result::Result<&Value, &Error>) {
+        let mut out = self.out.lock().unwrap();
+        let _ = match result {
+            Ok(args) => writeln!(out, "<- {} [{}] {}", info.method, info.tag,
+                                 redact_arguments(args.clone())),
+            Err(e) => writeln!(out, "<- {} [{}] error: {}", info.method, info.tag, e)
+        };
+    }
+}
+
+/// Measures how long the requests take. The callback is called after every request
+/// with the request, the duration and whether the request succeeded.
+pub struct Timing<F: Fn(&CallInfo, Duration, bool) + Send + Sync> {
+    callback: F,
+    started: Mutex<HashMap<usize, Instant>>
+}
+
+impl<F: Fn(&CallInfo, Duration, bool) + Send + Sync> Timing<F> {
+    pub fn new(callback: F) -> Timing<F> {
+        Timing {
+            callback: callback,
+            started: Mutex::new(HashMap::new())
+        }
+    }
+}
+
+impl<F: Fn(&CallInfo, Duration, bool) + Send + Sync> Interceptor for Timing<F> {
+    fn before(&self, info: &CallInfo, _request: &mut Value) -> Option<Result<Value>> {
+        self.started.lock().unwrap().insert(info.tag, Instant::now());
+        None
+    }
+
+    fn after(&self, info: &CallInfo, result: ::std::result::Result<&Value, &Error>) {
+        if let Some(started) = self.started.lock().unwrap().remove(&info.tag) {
+            (self.callback)(info, started.elapsed(), result.is_ok());
+        }
+    }
+}
+
+/// Returns true if the method only reads the daemon's state.
+pub fn is_read_only(method: &str) -> bool {
+    method.ends_with("-get") || method == "session-stats" || method == "free-space" ||
+        method == "port-test"
+}
+
+/// Prevents requests that modify the daemon's state from being sent. They fail with
+/// `Error::DryRun` instead, which `Error::is_dry_run` tells apart from real failures.
+/// Requests that only read the state are sent normally.
+pub struct DryRun;
+
+impl Interceptor for DryRun {
+    fn before(&self, info: &CallInfo, _request: &mut Value) -> Option<Result<Value>> {
+        if is_read_only(info.method) {
+            None
+        } else {
+            Some(Err(Error::DryRun(info.method.to_string())))
+        }
+    }
+}
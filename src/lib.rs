@@ -1,9 +1,6 @@
 #![feature(proc_macro, custom_derive, custom_attribute, plugin, associated_consts)]
 #![plugin(torrent_macro)]
-#![recursion_limit = "1024"]
 
-#[macro_use]
-extern crate error_chain;
 extern crate hyper;
 extern crate chrono;
 
@@ -13,34 +10,106 @@ extern crate serde_json;
 extern crate serde;
 
 extern crate rustc_serialize;
+extern crate rand;
+extern crate regex;
+extern crate openssl;
+extern crate openssl_verify;
+extern crate crossbeam;
+
+#[cfg(feature = "async")]
+extern crate reqwest;
+#[cfg(feature = "async")]
+#[macro_use]
+extern crate futures;
+#[cfg(feature = "async")]
+extern crate tokio;
+#[cfg(feature = "trace")]
+#[macro_use]
+extern crate tracing;
+
+#[macro_use]
+mod trace;
 
 pub mod error;
 pub mod requests;
 pub mod responses;
 pub mod types;
+pub mod transport;
+pub mod interceptor;
+pub mod query;
+#[cfg(any(test, feature = "fake-daemon"))]
+pub mod fake;
 mod torrent;
+mod capabilities;
+mod protocol;
+mod retry;
+mod batch;
+mod watcher;
+mod mirror;
+#[cfg(feature = "async")]
+mod async_client;
 
 #[cfg(test)]
 mod tests;
 
 pub use self::torrent::*;
+pub use self::capabilities::{Capabilities, RPC_VERSION};
+pub use self::protocol::Protocol;
+pub use self::retry::{RetryPolicy, RetryOn};
+pub use self::batch::Batch;
+pub use self::watcher::{Watcher, Events, Event};
+#[cfg(feature = "async")]
+pub use self::watcher::EventStream;
+pub use self::mirror::{Mirror, Change, Update};
+#[cfg(feature = "async")]
+pub use self::async_client::{AsyncTransmission, ResponseFuture};
 
 use error::*;
-use hyper::{Url, Client};
+use interceptor::{CallInfo, Interceptor};
+use hyper::Url;
 use hyper::status::StatusCode;
 use hyper::header::{ContentType, Headers, Authorization, Basic};
 use hyper::mime::{Mime, TopLevel, SubLevel, self};
-use requests::Request;
+use requests::{Request, GetSession};
 use responses::Response;
 use serde_json::Value;
+use transport::{Transport, TransportResponse, HttpTransport, HttpConfig, RecordingTransport, TlsConfig, Proxy};
+use std::{mem, thread};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use trace::CallSpan;
+
+/// The HTTP header used to pass the session id to the daemon.
+const SESSION_HEADER: &'static str = "X-Transmission-Session-Id";
+
+/// How many times the session id is renegotiated during a single request before giving up.
+const MAX_SESSION_RENEGOTIATIONS: usize = 3;
+
+/// How many requests `send_batch` sends at the same time by default.
+const DEFAULT_BATCH_LIMIT: usize = 8;
 
 /// A struct that represents the connection to the Transmission daemon.
+///
+/// `Transmission` is `Send` and `Sync`, so a single instance can be shared between
+/// threads and used to send requests concurrently.
 pub struct Transmission {
-    client:  Client,
+    transport: Box<Transport>,
+    custom_transport: bool,
+    /// The recorder wrapping the transport, if the requests are recorded
+    recorder: Option<Arc<RecordingTransport>>,
+    http:    HttpConfig,
+    deadline: Option<Duration>,
     auth:    Option<Basic>,
-    session: Option<String>,
+    session: RwLock<Option<String>>,
     url:     Url,
-    tag:     usize
+    tag:     AtomicUsize,
+    protocol: RwLock<Protocol>,
+    capabilities: RwLock<Option<Capabilities>>,
+    retry_policy: RetryPolicy,
+    interceptors: Vec<Box<Interceptor>>,
+    batch_limit: usize
 }
 
 impl Transmission {
@@ -48,17 +117,30 @@ impl Transmission {
     /// `http://127.0.0.1:9091/transmission/rpc` and no authentication.
     pub fn new() -> Transmission {
         Transmission {
-            client:  Client::new(),
+            transport: Box::new(HttpTransport::new()),
+            custom_transport: false,
+            recorder: None,
+            http:    HttpConfig::default(),
+            deadline: None,
             auth:    None,
-            session: None,
+            session: RwLock::new(None),
             url:    Url::parse("http://127.0.0.1:9091/transmission/rpc").unwrap(),
-            tag:     0
+            tag:     AtomicUsize::new(0),
+            protocol: RwLock::new(Protocol::Legacy),
+            capabilities: RwLock::new(None),
+            retry_policy: RetryPolicy::none(),
+            interceptors: Vec::new(),
+            batch_limit: DEFAULT_BATCH_LIMIT
         }
     }
 
-    /// Sets the url used to connect the daemon for future requests
+    /// Sets the url used to connect the daemon for future requests.
+    /// A url like `unix:///run/transmission/rpc.sock` connects to a daemon
+    /// listening on a Unix domain socket, see `set_unix_rpc_path`. The negotiated capabilities, and the protocol
+    /// selected based on them, are forgotten as they may not apply to the new daemon.
     pub fn set_url<U>(&mut self, url: U) -> &mut Self where Url: From<U> {
         self.url = Url::from(url) ;
+        self.reset_negotiation();
         self
     }
 
@@ -71,14 +153,200 @@ impl Transmission {
         self
     }
 
+    /// Sets the transport used to deliver the requests to the daemon.
+    /// Defaults to `HttpTransport`. The HTTP options, such as timeouts,
+    /// have no effect on a custom transport. Like `set_url`, this forgets
+    /// the negotiated capabilities and protocol.
+    pub fn set_transport<T>(&mut self, transport: T) -> &mut Self where T: Transport + 'static {
+        self.transport = Box::new(transport);
+        self.custom_transport = true;
+        self.recorder = None;
+        self.reset_negotiation();
+        self
+    }
+
+    /// Forgets the capabilities negotiated with the previous daemon and the protocol
+    /// selected based on them.
+    fn reset_negotiation(&mut self) {
+        if self.capabilities.write().unwrap().take().is_some() {
+            *self.protocol.write().unwrap() = Protocol::Legacy;
+        }
+    }
+
+    /// Recreates the default transport after the HTTP options have changed.
+    /// If the requests are recorded, the new transport is put under the recorder.
+    fn update_http(&mut self) {
+        if self.custom_transport {
+            return;
+        }
+
+        let http = Box::new(HttpTransport::with_config(&self.http));
+        match self.recorder {
+            Some(ref recorder) => recorder.set_inner(http),
+            None => self.transport = http
+        }
+    }
+
+    /// Sets the maximum time to wait for a connection to the daemon to be established.
+    pub fn set_connect_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.http.connect_timeout = timeout;
+        self.update_http();
+        self
+    }
+
+    /// Sets the maximum time to wait for data from the daemon.
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.http.read_timeout = timeout;
+        self.update_http();
+        self
+    }
+
+    /// Sets the maximum time to wait for the daemon to accept data.
+    pub fn set_write_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.http.write_timeout = timeout;
+        self.update_http();
+        self
+    }
+
+    /// Sets the TLS options used when connecting to the daemon over HTTPS.
+    /// Returns an error if the certificates can't be loaded.
+    ///
+    /// ```no_run
+    /// # use transmission_rpc::Transmission;
+    /// # use transmission_rpc::transport::TlsConfig;
+    /// let mut tr = Transmission::new();
+    /// tr.set_tls(TlsConfig::new()
+    ///     .add_root_certificate("/etc/ssl/internal-ca.pem")
+    ///     .client_certificate("client.pem", "client.key")).unwrap();
+    /// ```
+    pub fn set_tls(&mut self, config: TlsConfig) -> Result<&mut Self> {
+        self.http.tls = Some(config.build()?);
+        self.update_http();
+        Ok(self)
+    }
+
+    /// Sets the proxy through which the daemon is connected. Both HTTP proxies,
+    /// which are used through the `CONNECT` method, and SOCKS5 proxies are supported.
+    pub fn set_proxy(&mut self, proxy: Option<Proxy>) -> &mut Self {
+        self.http.proxy = proxy;
+        self.update_http();
+        self
+    }
+
+    /// Reads the proxy from the `HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY` and
+    /// `NO_PROXY` environment variables if no proxy has been set explicitly.
+    pub fn set_proxy_from_env(&mut self, enabled: bool) -> &mut Self {
+        self.http.proxy_from_env = enabled;
+        self.update_http();
+        self
+    }
+
+    /// Sets the path the requests are sent to when the url is a Unix domain socket,
+    /// e.g. when a reverse proxy in front of the daemon listens on the socket.
+    /// Defaults to `/transmission/rpc`.
+    pub fn set_unix_rpc_path(&mut self, path: Option<String>) -> &mut Self {
+        self.http.unix_rpc_path = path;
+        self.update_http();
+        self
+    }
+
+    /// Sets the maximum total time spent on a single call to `send`, including the
+    /// session id handshake and the retries. The timeouts of each attempt are limited
+    /// to the time left before the deadline, so a hung daemon fails the call with
+    /// `transport::Error::Timeout`. If a retry doesn't fit before the deadline, the
+    /// error of the last attempt is returned instead.
+    ///
+    /// Custom transports have to implement `Transport::post_timeout` to honor the
+    /// deadline within an attempt.
+    pub fn set_deadline(&mut self, deadline: Option<Duration>) -> &mut Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Sets the policy used to retry failed requests. By default requests are not retried.
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) -> &mut Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Sets the maximum number of requests `send_batch` sends at the same time.
+    /// Defaults to 8.
+    pub fn set_batch_limit(&mut self, limit: usize) -> &mut Self {
+        self.batch_limit = limit;
+        self
+    }
+
+    /// Adds an interceptor that is run around every request. See the `interceptor` module.
+    ///
+    /// ```no_run
+    /// # use transmission_rpc::Transmission;
+    /// use transmission_rpc::interceptor::{DryRun, Logger};
+    ///
+    /// let mut tr = Transmission::new();
+    /// tr.add_interceptor(Logger::stderr())
+    ///   .add_interceptor(DryRun);
+    /// ```
+    pub fn add_interceptor<I: Interceptor + 'static>(&mut self, interceptor: I) -> &mut Self {
+        self.interceptors.push(Box::new(interceptor));
+        self
+    }
+
+    /// Records the requests sent using the current transport and the responses to them
+    /// into a cassette file. The cassette can be replayed using `ReplayTransport`.
+    /// The HTTP options set afterwards still apply to the recorded requests; setting
+    /// a custom transport stops the recording.
+    pub fn record<P: Into<PathBuf>>(&mut self, path: P) -> &mut Self {
+        let inner = mem::replace(&mut self.transport, Box::new(HttpTransport::new()));
+        let recorder = Arc::new(RecordingTransport::new(inner, path));
+        self.transport = Box::new(recorder.clone());
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Sets the protocol used in future requests. The protocol is also selected
+    /// automatically by `negotiate` based on the daemon's RPC version.
+    pub fn set_protocol(&mut self, protocol: Protocol) -> &mut Self {
+        *self.protocol.write().unwrap() = protocol;
+        self
+    }
+
+    /// Returns the protocol used to talk to the daemon.
+    pub fn protocol(&self) -> Protocol {
+        *self.protocol.read().unwrap()
+    }
+
+    /// Returns the capabilities of the daemon if they have been negotiated.
+    pub fn capabilities(&self) -> Option<Capabilities> {
+        self.capabilities.read().unwrap().clone()
+    }
+
+    /// Queries the daemon's RPC version and caches it. Returns an error if the daemon
+    /// no longer supports the version of the RPC interface used by this library.
+    /// Switches to the JSON-RPC 2.0 protocol if the daemon supports it.
+    ///
+    /// This is done automatically by `send` the first time a request that requires
+    /// a newer RPC version than the first one is sent.
+    pub fn negotiate(&self) -> Result<Capabilities> {
+        let session = self.send(&GetSession::new())?;
+        let capabilities = Capabilities::from(session);
+
+        if !capabilities.is_compatible() {
+            return Err(error::protocol::Error::Incompatible(capabilities.rpc_version_minimum).into());
+        }
+
+        *self.protocol.write().unwrap() = Protocol::for_rpc_version(capabilities.rpc_version);
+        *self.capabilities.write().unwrap() = Some(capabilities.clone());
+        Ok(capabilities)
+    }
+
     fn headers(&self) -> Headers {
         let mut headers = Headers::new();
 
         headers.set(ContentType(Mime(TopLevel::Application, SubLevel::Json,
                                      vec![(mime::Attr::Charset, mime::Value::Utf8)])));
 
-        if let Some(ref key) = self.session {
-            headers.set_raw("X-Transmission-Session-Id", vec![key.clone().into_bytes()]);
+        if let Some(ref key) = *self.session.read().unwrap() {
+            headers.set_raw(SESSION_HEADER, vec![key.clone().into_bytes()]);
         }
         
         if let Some(ref auth) = self.auth {
@@ -89,50 +357,187 @@ impl Transmission {
     }
 
     /// Sends given request to the daemon and returns the received response.
-    pub fn send<R>(&mut self, request: &R) -> Result<R::Response>
+    /// Each request is given a unique tag which is included in the error
+    /// if the request fails. Failed requests are retried according to the retry policy.
+    pub fn send<R>(&self, request: &R) -> Result<R::Response>
+        where R: Request, R::Response: Response
+    {
+        let tag = self.tag.fetch_add(1, Ordering::Relaxed);
+
+        let deadline = self.deadline.map(|d| Instant::now() + d);
+
+        let span = CallSpan::new(request.method_name(), tag, &self.url);
+        let result = span.in_scope(|| {
+            let mut attempt: u32 = 1;
+            loop {
+                span.attempt(attempt);
+                let e = match self.send_tagged(request, tag, deadline, &span) {
+                    Err(e) => e,
+                    result => return result
+                };
+
+                // The last error is returned if there is no time left for another attempt,
+                // so a failing daemon can be told apart from a slow one.
+                let delay = self.retry_policy.delay(attempt);
+                if !self.retry_policy.should_retry(&e, attempt, request.is_idempotent()) ||
+                    deadline.map(|d| Instant::now() + delay >= d).unwrap_or(false)
+                {
+                    return Err(e.in_call(request.method_name(), tag, None));
+                }
+
+                trace_debug!(error = %e, "retrying in {:?}", delay);
+                thread::sleep(delay);
+                attempt += 1;
+            }
+        });
+
+        span.finish(&result);
+        result
+    }
+
+    /// Sends the requests concurrently, at most `set_batch_limit` at a time, and returns
+    /// the results in the same order. See `Batch` for the supported groups of requests.
+    ///
+    /// ```no_run
+    /// # use transmission_rpc::Transmission;
+    /// # use transmission_rpc::requests::{GetSession, GetTorrent};
+    /// let tr = Transmission::new();
+    /// let (session, torrents) = tr.send_batch(&(GetSession::new(), GetTorrent::new()));
+    /// ```
+    pub fn send_batch<B: Batch + ?Sized>(&self, batch: &B) -> B::Responses {
+        batch.send_all(self, self.batch_limit)
+    }
+
+    /// Posts the request to the daemon within the time left before the deadline.
+    fn post(&self, body: &str, deadline: Option<Instant>) -> Result<TransportResponse> {
+        match deadline {
+            Some(deadline) => {
+                let now = Instant::now();
+                if now >= deadline {
+                    return Err(error::transport::Error::Timeout.into());
+                }
+                self.transport.post_timeout(&self.url, self.headers(), body.as_bytes(), deadline - now)
+            },
+            None => self.transport.post(&self.url, self.headers(), body.as_bytes())
+        }
+    }
+
+    fn send_tagged<R>(&self, request: &R, tag: usize, deadline: Option<Instant>,
+                      span: &CallSpan) -> Result<R::Response>
         where R: Request, R::Response: Response
-    {    
-        let req_str = serde_json::to_string(&request.to_value())
-            .map_err(|e| ::error::deserialize::Error::from(e))?;
+    {
+        let required = request.min_rpc_version();
+        let capabilities = match self.capabilities() {
+            Some(capabilities) => Some(capabilities),
+            None if required > 1 => Some(self.negotiate()?),
+            None => None
+        };
 
-        let mut response = try!(self.client.post(self.url.clone())
-            .headers(self.headers())
-            .body(&*req_str)
-            .send());
+        if let Some(capabilities) = capabilities {
+            if !capabilities.supports(required) {
+                return Err(error::protocol::Error::Unsupported {
+                    method: request.method_name().to_string(),
+                    required: required,
+                    available: capabilities.rpc_version
+                }.into());
+            }
+        }
+
+        let protocol = self.protocol();
+        let info = CallInfo {
+            method: request.method_name(),
+            tag: tag,
+            protocol: protocol
+        };
+        let mut req_value = protocol.encode(request, tag);
+
+        let mut intercepted = None;
+        let mut called = 0;
+        for interceptor in &self.interceptors {
+            called += 1;
+            intercepted = interceptor.before(&info, &mut req_value);
+            if intercepted.is_some() {
+                break;
+            }
+        }
+
+        let (result, body) = match intercepted {
+            Some(result) => (result, None),
+            None => match self.exchange(&req_value, deadline, span) {
+                Ok(response) => (Self::read_response(protocol, &response, tag), Some(response.body)),
+                Err(e) => (Err(e), None)
+            }
+        };
+
+        // The interceptors see the arguments only if they could be deserialized.
+        let mut args_seen = None;
+        let response = result.and_then(|args| {
+            if called > 0 {
+                args_seen = Some(args.clone());
+            }
+            Ok(R::Response::from_value(args)?)
+        });
+
+        for interceptor in self.interceptors[..called].iter().rev() {
+            match (&response, &args_seen) {
+                (&Ok(_), &Some(ref args)) => interceptor.after(&info, Ok(args)),
+                (&Err(ref e), _) => interceptor.after(&info, Err(e)),
+                _ => ()
+            }
+        }
+
+        // Errors caused by the response include the beginning of it to ease debugging.
+        response.map_err(|e| match body {
+                Some(ref body) => e.in_call(request.method_name(), tag, Some(&body[..])),
+                None => e
+            })
+    }
+
+    /// Sends the request and returns the response once the session id has been agreed on.
+    fn exchange(&self, req_value: &Value, deadline: Option<Instant>, span: &CallSpan) -> Result<TransportResponse> {
+        trace_debug!(request = %trace::redact_arguments(req_value.clone()), "sending request");
+        let req_str = serde_json::to_string(req_value)?;
+
+        let mut response = self.post(&req_str, deadline)?;
 
         // X-Transmission-Session-Id HTTP header must be set to correct value for the
         // daemon to accept the request. The daemon returns the wanted value for the
         // header on every response and changes that value from time to time.
         // HTTP status code 901 or `Conflict` means that the daemon received an invalid
         // or no session id.
-        if response.status == StatusCode::Conflict {
-            // Get the correct session id from the response. 
-            self.session = Some(String::from_utf8(response.headers.get_raw("X-Transmission-Session-Id").unwrap()[0].clone()).unwrap());
+        let mut renegotiations = 0;
+        while response.status == StatusCode::Conflict {
+            if renegotiations == MAX_SESSION_RENEGOTIATIONS {
+                return Err(error::protocol::Error::SessionConflict(renegotiations).into());
+            }
+            renegotiations += 1;
+            trace_debug!(renegotiations, "renegotiating the session id");
+
+            // Get the correct session id from the response.
+            let session = response.headers.get_raw(SESSION_HEADER)
+                .and_then(|values| values.get(0))
+                .and_then(|value| String::from_utf8(value.clone()).ok())
+                .ok_or(error::protocol::Error::MissingSessionId)?;
+            *self.session.write().unwrap() = Some(session);
 
             // Try again using the correct id.
-            response = try!(self.client.post(self.url.clone())
-                .headers(self.headers())
-                .body(&*req_str)
-                .send());
+            response = self.post(&req_str, deadline)?;
         }
+        span.response_size(response.body.len());
 
+        Ok(response)
+    }
+
+    /// Returns the arguments of the response.
+    fn read_response(protocol: Protocol, response: &TransportResponse, tag: usize) -> Result<Value> {
         // If the daemon responded with status other than 200 return an error.
-        // Return also if the second try with the new session id failed.
         if response.status != StatusCode::Ok {
-            bail!(::error::ErrorKind::Daemon(::error::daemon::ErrorKind::StatusCode(response.status)));
+            return Err(daemon::from_status(response.status, &response.body).into());
         }
-        
-        let value: Value = serde_json::from_reader(&mut response)
-            .map_err(|e| ::error::ErrorKind::Deserialize(::error::deserialize::ErrorKind::Json(e)))?;
-        let obj = value.as_object().unwrap();
 
-        if let Some(&Value::String(ref result)) = obj.get("result") {
-            if result != &"success".to_string() {
-                bail!(ErrorKind::Daemon(::error::daemon::ErrorKind::Message(result.clone())));
-            }
-        }
-        
-        let args = obj.get("arguments").unwrap();
-        Ok(R::Response::from_value(args.clone())?)
+        let value: Value = serde_json::from_slice(&response.body)?;
+        trace_debug!(response = %trace::redact_arguments(value.clone()), "received response");
+
+        protocol.decode(value, tag)
     }
 }
@@ -0,0 +1,185 @@
+//! A local copy of the daemon's torrents that is kept up to date incrementally.
+
+use error::Result;
+use requests::GetTorrent;
+use std::collections::BTreeMap;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::Instant;
+use torrent::{Torrent, TorrentField};
+use watcher::is_recently_active;
+use Transmission;
+
+/// The fields requested by default. They are supported by every RPC version and
+/// are cheap to fetch on every sync, unlike e.g. the files or the peers.
+const DEFAULT_FIELDS: [TorrentField; 10] = [TorrentField::Id, TorrentField::Name, TorrentField::Status,
+                                            TorrentField::PercentDone, TorrentField::RateDownload,
+                                            TorrentField::RateUpload, TorrentField::UploadRatio,
+                                            TorrentField::TotalSize, TorrentField::Error,
+                                            TorrentField::ErrorString];
+
+/// A change to a torrent in a `Mirror`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Change {
+    /// The torrent with the id was added.
+    Added(u64),
+    /// The torrent with the id was active and its fields were refreshed.
+    Updated(u64),
+    /// The torrent with the id was removed.
+    Removed(u64)
+}
+
+/// The changes made by a single `Mirror::sync`, sent to the subscribers.
+#[derive(Debug, Clone)]
+pub struct Update {
+    /// The revision of the mirror after the changes
+    pub revision: u64,
+    /// The changed torrents
+    pub changes: Vec<Change>
+}
+
+/// A local copy of the daemon's torrents.
+///
+/// The first `sync` fetches every torrent and the later ones fetch only the torrents
+/// that the daemon reports as recently active, along with the ids of the recently
+/// removed torrents. The daemon reports the changes of the last minute, so if more time
+/// has passed since the previous successful sync, every torrent is fetched again.
+/// Each sync that changes the torrents increments the revision and sends the changes
+/// to the subscribers. The torrents are replaced only by `sync`, so they are always
+/// consistent with a single revision.
+///
+/// ```no_run
+/// # use transmission_rpc::{Transmission, Mirror, TorrentField};
+/// let tr = Transmission::new();
+/// let mut mirror = Mirror::new().fields(vec![TorrentField::Name, TorrentField::PercentDone]);
+/// mirror.sync(&tr).unwrap();
+/// for (id, torrent) in mirror.torrents() {
+///     println!("{}: {:?}", id, torrent.name);
+/// }
+/// ```
+pub struct Mirror {
+    fields: Vec<TorrentField>,
+    torrents: BTreeMap<u64, Torrent>,
+    revision: u64,
+    /// When the last successful sync was sent, if the mirror is synced
+    synced_at: Option<Instant>,
+    subscribers: Vec<Sender<Update>>
+}
+
+impl Mirror {
+    /// Creates an empty mirror that requests the id, name, status, progress, transfer
+    /// rates, upload ratio, total size and error of the torrents. Use `fields` to
+    /// request others.
+    pub fn new() -> Mirror {
+        Mirror {
+            fields: DEFAULT_FIELDS.to_vec(),
+            torrents: BTreeMap::new(),
+            revision: 0,
+            synced_at: None,
+            subscribers: Vec::new()
+        }
+    }
+
+    /// Sets the fields that are requested. The id is always requested.
+    /// Changing the fields of a synced mirror makes the next sync fetch every torrent.
+    pub fn fields(mut self, fields: Vec<TorrentField>) -> Self {
+        self.fields = fields;
+        if !self.fields.contains(&TorrentField::Id) {
+            self.fields.insert(0, TorrentField::Id);
+        }
+        self.synced_at = None;
+        self
+    }
+
+    /// Returns the torrents by their id.
+    pub fn torrents(&self) -> &BTreeMap<u64, Torrent> {
+        &self.torrents
+    }
+
+    /// Returns the torrent with the id.
+    pub fn get(&self, id: u64) -> Option<&Torrent> {
+        self.torrents.get(&id)
+    }
+
+    /// Returns the revision, which is incremented by every sync that changes the torrents.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Returns a receiver that gets the changes made by the following syncs.
+    pub fn subscribe(&mut self) -> Receiver<Update> {
+        let (sender, receiver) = channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+
+    /// Makes the next sync fetch every torrent again.
+    pub fn invalidate(&mut self) {
+        self.synced_at = None;
+    }
+
+    /// Fetches the changes from the daemon and applies them. Returns the new revision.
+    /// If the request fails the mirror is left unchanged.
+    pub fn sync(&mut self, tr: &Transmission) -> Result<u64> {
+        let incremental = self.synced_at.map(is_recently_active).unwrap_or(false);
+        let request = if incremental {
+            GetTorrent::recently_active()
+        } else {
+            GetTorrent::new()
+        };
+        let started = Instant::now();
+        let response = tr.send(&request.fields(self.fields.clone()))?;
+        self.synced_at = Some(started);
+
+        let mut changes = Vec::new();
+        let mut torrents = BTreeMap::new();
+
+        if incremental {
+            for id in response.removed() {
+                if self.torrents.remove(id).is_some() {
+                    changes.push(Change::Removed(*id));
+                }
+            }
+        }
+
+        for torrent in response {
+            if let Some(id) = torrent.id {
+                torrents.insert(id, torrent);
+            }
+        }
+
+        if incremental {
+            for (id, torrent) in torrents {
+                changes.push(match self.torrents.insert(id, torrent) {
+                    Some(_) => Change::Updated(id),
+                    None => Change::Added(id)
+                });
+            }
+        } else {
+            // A full fetch replaces everything; the torrents missing from it were removed.
+            for id in self.torrents.keys() {
+                if !torrents.contains_key(id) {
+                    changes.push(Change::Removed(*id));
+                }
+            }
+            for id in torrents.keys() {
+                changes.push(if self.torrents.contains_key(id) {
+                    Change::Updated(*id)
+                } else {
+                    Change::Added(*id)
+                });
+            }
+            self.torrents = torrents;
+        }
+
+        if !changes.is_empty() {
+            self.revision += 1;
+            let update = Update {
+                revision: self.revision,
+                changes: changes
+            };
+            self.subscribers.retain(|subscriber| subscriber.send(update.clone()).is_ok());
+        }
+
+        Ok(self.revision)
+    }
+}
@@ -0,0 +1,253 @@
+//! Encoding of requests and decoding of responses for the protocols spoken by the daemon.
+//!
+//! The legacy protocol uses `method` and `arguments` keys, kebab-case method names and
+//! a mix of camelCase and kebab-case argument names. The JSON-RPC 2.0 protocol introduced
+//! in Transmission 4.1 uses snake_case for both. The rest of the library works with the
+//! legacy names, so the JSON-RPC messages are translated to and from them here.
+
+use error::*;
+use error::daemon::{JsonRpcCode, MessageKind};
+use requests::Request;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// The first version of the RPC interface that supports JSON-RPC 2.0.
+pub const JSONRPC_RPC_VERSION: u64 = 18;
+
+/// The protocol used to talk to the daemon.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Protocol {
+    /// The original protocol supported by every version of the daemon.
+    Legacy,
+    /// JSON-RPC 2.0, supported since Transmission 4.1.
+    JsonRpc
+}
+
+impl Protocol {
+    /// Returns the preferred protocol for a daemon with the given RPC version.
+    pub fn for_rpc_version(rpc_version: u64) -> Protocol {
+        if rpc_version >= JSONRPC_RPC_VERSION {
+            Protocol::JsonRpc
+        } else {
+            Protocol::Legacy
+        }
+    }
+
+    /// Creates the JSON object that is sent to the daemon.
+    pub fn encode<R: Request>(&self, request: &R, tag: usize) -> Value {
+        match *self {
+            Protocol::Legacy => request.to_value(tag),
+            Protocol::JsonRpc => Value::Object({
+                let mut obj = BTreeMap::new();
+                obj.insert("jsonrpc".to_string(), Value::String("2.0".to_string()));
+                obj.insert("id".to_string(), Value::U64(tag as u64));
+                obj.insert("method".to_string(),
+                           Value::String(to_snake_case(request.method_name())));
+                obj.insert("params".to_string(), snake_case_arguments(request.arguments()));
+                obj
+            })
+        }
+    }
+
+    /// Extracts the arguments from the daemon's response or returns the error
+    /// the daemon responded with. Returns an error if the response is for
+    /// a request with a different tag.
+    pub fn decode(&self, value: Value, tag: usize) -> Result<Value> {
+        let mut obj = match value {
+            Value::Object(obj) => obj,
+            _ => return Err(deserialize::Error::InvalidType("object".to_string(),
+                                                            "response".to_string()).into())
+        };
+
+        let tag_key = match *self {
+            Protocol::Legacy => "tag",
+            Protocol::JsonRpc => "id"
+        };
+
+        // The daemon always echoes the tag, so a missing one is a mismatch too.
+        let received = obj.get(tag_key).and_then(|t| t.as_u64());
+        if received != Some(tag as u64) {
+            return Err(protocol::Error::TagMismatch {
+                expected: tag,
+                received: received.map(|t| t as usize)
+            }.into());
+        }
+
+        match *self {
+            Protocol::Legacy => {
+                if let Some(&Value::String(ref result)) = obj.get("result") {
+                    if result != &"success".to_string() {
+                        return Err(daemon::Error::Message(MessageKind::classify(result),
+                                                          result.clone()).into());
+                    }
+                }
+
+                obj.remove("arguments")
+                    .ok_or_else(|| deserialize::Error::MissingField("arguments".to_string()).into())
+            },
+            Protocol::JsonRpc => {
+                if let Some(error) = obj.remove("error") {
+                    return Err(json_rpc_error(error).into());
+                }
+
+                obj.remove("result")
+                    .map(legacy_arguments)
+                    .ok_or_else(|| deserialize::Error::MissingField("result".to_string()).into())
+            }
+        }
+    }
+}
+
+/// Converts a JSON-RPC error object into a daemon error.
+fn json_rpc_error(error: Value) -> daemon::Error {
+    let code = error.find("code").and_then(|c| c.as_i64()).unwrap_or(0);
+
+    // Transmission puts the legacy error message into `data.error_string`, which is
+    // more descriptive than the generic message.
+    let message = error.lookup("data.error_string")
+        .or_else(|| error.find("message"))
+        .and_then(|m| m.as_str())
+        .unwrap_or("")
+        .to_string();
+
+    daemon::Error::JsonRpc(JsonRpcCode::from_code(code), MessageKind::classify(&message), message)
+}
+
+/// Converts the names of the request's arguments, and the field names in the
+/// `fields` list, to snake_case.
+fn snake_case_arguments(args: Value) -> Value {
+    match args {
+        Value::Object(obj) => Value::Object(obj.into_iter().map(|(key, value)| {
+            let value = if key == "fields" {
+                map_strings(value, to_snake_case)
+            } else {
+                snake_case_arguments(value)
+            };
+            (to_snake_case(&key), value)
+        }).collect()),
+        Value::Array(values) => Value::Array(values.into_iter().map(snake_case_arguments).collect()),
+        value => value
+    }
+}
+
+/// The legacy names at the top level of the responses that are not in kebab-case.
+const TOP_LEVEL_NAMES: [&'static str; 8] = [
+    // session-get
+    "seedRatioLimit", "seedRatioLimited",
+    // session-stats
+    "activeTorrentCount", "downloadSpeed", "pausedTorrentCount", "torrentCount", "uploadSpeed",
+    // free-space
+    "total_size"
+];
+
+/// The legacy names in the nested objects of the responses that are not in camelCase.
+const NESTED_NAMES: [&'static str; 8] = [
+    // torrent-get
+    "file-count", "primary-mime-type",
+    // the units of session-get
+    "speed-units", "speed-bytes", "size-units", "size-bytes", "memory-units", "memory-bytes"
+];
+
+/// Returns the legacy name of a snake_case name. The top level names are in kebab-case
+/// (e.g. `rpc-version`, `torrent-added`) and the names in the nested objects in camelCase
+/// (e.g. `hashString`), except for the names listed above.
+fn legacy_name(name: &str, top_level: bool) -> String {
+    let exceptions: &[&str] = if top_level { &TOP_LEVEL_NAMES } else { &NESTED_NAMES };
+
+    match exceptions.iter().find(|legacy| to_snake_case(legacy) == name) {
+        Some(legacy) => legacy.to_string(),
+        None if top_level => to_kebab_case(name),
+        None => to_camel_case(name)
+    }
+}
+
+/// Converts the names in a JSON-RPC result back to those used by the legacy protocol.
+fn legacy_arguments(result: Value) -> Value {
+    match result {
+        Value::Object(obj) => Value::Object(obj.into_iter().map(|(key, value)| {
+            let value = if key == "torrents" {
+                legacy_torrents(value)
+            } else {
+                legacy_keys(value)
+            };
+            (legacy_name(&key, true), value)
+        }).collect()),
+        value => value
+    }
+}
+
+/// Converts the torrents of a `torrent-get` response. The header row of the table
+/// format contains the field names as strings.
+fn legacy_torrents(torrents: Value) -> Value {
+    match torrents {
+        Value::Array(mut rows) => {
+            if let Some(&Value::Array(_)) = rows.first() {
+                let header = map_strings(rows.remove(0), |name| legacy_name(name, false));
+                rows.insert(0, header);
+                Value::Array(rows)
+            } else {
+                legacy_keys(Value::Array(rows))
+            }
+        },
+        value => value
+    }
+}
+
+/// Converts the names in the nested objects.
+fn legacy_keys(value: Value) -> Value {
+    match value {
+        Value::Object(obj) => Value::Object(obj.into_iter()
+                                            .map(|(key, value)| (legacy_name(&key, false), legacy_keys(value)))
+                                            .collect()),
+        Value::Array(values) => Value::Array(values.into_iter().map(legacy_keys).collect()),
+        value => value
+    }
+}
+
+fn map_strings<F>(value: Value, f: F) -> Value where F: Fn(&str) -> String {
+    match value {
+        Value::Array(values) => Value::Array(values.into_iter().map(|v| match v {
+            Value::String(s) => Value::String(f(&s)),
+            v => v
+        }).collect()),
+        value => value
+    }
+}
+
+/// `hashString` and `download-dir` become `hash_string` and `download_dir`.
+fn to_snake_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len() + 4);
+    for ch in name.chars() {
+        if ch == '-' {
+            out.push('_');
+        } else if ch.is_uppercase() {
+            out.push('_');
+            out.extend(ch.to_lowercase());
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
+
+/// `hash_string` becomes `hashString`.
+fn to_camel_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut upper = false;
+    for ch in name.chars() {
+        if ch == '_' {
+            upper = true;
+        } else if upper {
+            out.extend(ch.to_uppercase());
+            upper = false;
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
+
+/// `rpc_version` becomes `rpc-version`.
+fn to_kebab_case(name: &str) -> String {
+    name.replace('_', "-")
+}
@@ -0,0 +1,330 @@
+//! Filtering and sorting torrents on the client.
+
+use chrono::NaiveDateTime;
+use hyper::Url;
+use regex::Regex;
+use requests::GetTorrent;
+use std::cmp::Ordering;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use torrent::{Torrent, TorrentField};
+use types::Status;
+
+/// A condition on a torrent.
+#[derive(Debug, Clone)]
+enum Filter {
+    Status(Status),
+    Label(String),
+    TrackerHost(String),
+    RatioAbove(f64),
+    RatioBelow(f64),
+    SizeAbove(u64),
+    SizeBelow(u64),
+    AddedBefore(Duration),
+    AddedWithin(Duration),
+    Name(Regex),
+    Error(bool),
+    All(Vec<Filter>),
+    Any(Vec<Filter>),
+    Not(Box<Filter>)
+}
+
+impl Filter {
+    fn fields(&self, fields: &mut Vec<TorrentField>) {
+        let field = match *self {
+            Filter::Status(_) => TorrentField::Status,
+            Filter::Label(_) => TorrentField::Labels,
+            Filter::TrackerHost(_) => TorrentField::Trackers,
+            Filter::RatioAbove(_) | Filter::RatioBelow(_) => TorrentField::UploadRatio,
+            Filter::SizeAbove(_) | Filter::SizeBelow(_) => TorrentField::TotalSize,
+            Filter::AddedBefore(_) | Filter::AddedWithin(_) => TorrentField::AddedDate,
+            Filter::Name(_) => TorrentField::Name,
+            Filter::Error(_) => TorrentField::Error,
+            Filter::All(ref filters) | Filter::Any(ref filters) => {
+                for filter in filters {
+                    filter.fields(fields);
+                }
+                return;
+            },
+            Filter::Not(ref filter) => return filter.fields(fields)
+        };
+        add_field(fields, field);
+    }
+
+    /// Returns `None` if a field needed by the filter is missing, so that negating
+    /// the filter doesn't match the torrents that lack the field either.
+    fn eval(&self, torrent: &Torrent) -> Option<bool> {
+        match *self {
+            Filter::Status(status) => torrent.status.map(|s| s == status),
+            Filter::Label(ref label) => torrent.labels.as_ref().map(|labels| labels.contains(label)),
+            Filter::TrackerHost(ref host) => torrent.trackers.as_ref()
+                .map(|trackers| trackers.iter().any(|tracker| {
+                    Url::parse(&tracker.announce).ok()
+                        .and_then(|url| url.host_str().map(|h| h.to_lowercase().contains(host)))
+                        .unwrap_or(false)
+                })),
+            Filter::RatioAbove(ratio) => torrent.upload_ratio.map(|r| r > ratio),
+            Filter::RatioBelow(ratio) => torrent.upload_ratio.map(|r| r < ratio),
+            Filter::SizeAbove(size) => torrent.total_size.map(|s| s > size),
+            Filter::SizeBelow(size) => torrent.total_size.map(|s| s < size),
+            Filter::AddedBefore(age) => torrent.added_date.map(|d| d < ago(age)),
+            Filter::AddedWithin(age) => torrent.added_date.map(|d| d >= ago(age)),
+            Filter::Name(ref regex) => torrent.name.as_ref().map(|n| regex.is_match(n)),
+            Filter::Error(error) => torrent.error.map(|e| (e != 0) == error),
+            Filter::All(ref filters) => combine(filters, torrent, false),
+            Filter::Any(ref filters) => combine(filters, torrent, true),
+            Filter::Not(ref filter) => filter.eval(torrent).map(|result| !result)
+        }
+    }
+}
+
+/// Evaluates `All` if `decisive` is false and `Any` if it is true. A filter that
+/// evaluates to `decisive` decides the result even if the others lack their fields.
+fn combine(filters: &[Filter], torrent: &Torrent, decisive: bool) -> Option<bool> {
+    let mut missing = false;
+    for filter in filters {
+        match filter.eval(torrent) {
+            Some(result) if result == decisive => return Some(decisive),
+            Some(_) => (),
+            None => missing = true
+        }
+    }
+    if missing { None } else { Some(!decisive) }
+}
+
+fn add_field(fields: &mut Vec<TorrentField>, field: TorrentField) {
+    if !fields.contains(&field) {
+        fields.push(field);
+    }
+}
+
+/// Returns the time `age` ago.
+fn ago(age: Duration) -> NaiveDateTime {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or(Duration::from_secs(0));
+    let secs = now.as_secs().saturating_sub(age.as_secs());
+    NaiveDateTime::from_timestamp(secs as i64, 0)
+}
+
+/// The key the torrents are sorted by.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Name,
+    Ratio,
+    Size,
+    Added,
+    Progress
+}
+
+impl SortKey {
+    fn field(&self) -> TorrentField {
+        match *self {
+            SortKey::Name => TorrentField::Name,
+            SortKey::Ratio => TorrentField::UploadRatio,
+            SortKey::Size => TorrentField::TotalSize,
+            SortKey::Added => TorrentField::AddedDate,
+            SortKey::Progress => TorrentField::PercentDone
+        }
+    }
+
+    /// Compares the torrents, in reverse if `descending` is true. The torrents missing
+    /// the field are the greatest either way, so they are sorted last.
+    fn compare(&self, a: &Torrent, b: &Torrent, descending: bool) -> Ordering {
+        fn by<T, F: Fn(&T, &T) -> Ordering>(a: Option<T>, b: Option<T>, cmp: F,
+                                             descending: bool) -> Ordering {
+            match (a, b) {
+                (Some(a), Some(b)) => if descending { cmp(&a, &b).reverse() } else { cmp(&a, &b) },
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal
+            }
+        }
+        let floats = |a: &f64, b: &f64| a.partial_cmp(b).unwrap_or(Ordering::Equal);
+
+        match *self {
+            SortKey::Name => by(a.name.as_ref(), b.name.as_ref(), |a, b| a.cmp(b), descending),
+            SortKey::Ratio => by(a.upload_ratio, b.upload_ratio, floats, descending),
+            SortKey::Size => by(a.total_size, b.total_size, |a, b| a.cmp(b), descending),
+            SortKey::Added => by(a.added_date, b.added_date, |a, b| a.cmp(b), descending),
+            SortKey::Progress => by(a.percent_done, b.percent_done, floats, descending)
+        }
+    }
+}
+
+/// A query over torrents, e.g. "seeding, ratio above 2, labeled tv". All conditions
+/// added to a query must match; use `or` and `not` to combine them differently.
+/// A condition doesn't match a torrent that lacks the field it needs, and neither
+/// does its negation.
+///
+/// `fields` returns the fields needed to evaluate the query, so only those have
+/// to be requested from the daemon.
+///
+/// ```no_run
+/// # use transmission_rpc::Transmission;
+/// # use transmission_rpc::query::{Query, SortKey};
+/// # use transmission_rpc::types::Status;
+/// let tr = Transmission::new();
+/// let query = Query::new()
+///     .status(Status::Seed)
+///     .ratio_above(2.0)
+///     .label("tv")
+///     .sort_by(SortKey::Ratio, true)
+///     .limit(10);
+///
+/// let torrents = query.apply(tr.send(&query.request()).unwrap());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Query {
+    filters: Vec<Filter>,
+    sort: Option<(SortKey, bool)>,
+    limit: Option<usize>
+}
+
+impl Query {
+    /// Creates a query that matches every torrent.
+    pub fn new() -> Query {
+        Query {
+            filters: Vec::new(),
+            sort: None,
+            limit: None
+        }
+    }
+
+    fn filter(mut self, filter: Filter) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    /// Matches the torrents with the status.
+    pub fn status(self, status: Status) -> Self {
+        self.filter(Filter::Status(status))
+    }
+
+    /// Matches the torrents with the label.
+    pub fn label<S: Into<String>>(self, label: S) -> Self {
+        self.filter(Filter::Label(label.into()))
+    }
+
+    /// Matches the torrents with a tracker whose host name contains `host`, ignoring case.
+    pub fn tracker_host<S: Into<String>>(self, host: S) -> Self {
+        self.filter(Filter::TrackerHost(host.into().to_lowercase()))
+    }
+
+    /// Matches the torrents with an upload ratio greater than `ratio`.
+    pub fn ratio_above(self, ratio: f64) -> Self {
+        self.filter(Filter::RatioAbove(ratio))
+    }
+
+    /// Matches the torrents with an upload ratio less than `ratio`.
+    pub fn ratio_below(self, ratio: f64) -> Self {
+        self.filter(Filter::RatioBelow(ratio))
+    }
+
+    /// Matches the torrents whose total size in bytes is greater than `size`.
+    pub fn size_above(self, size: u64) -> Self {
+        self.filter(Filter::SizeAbove(size))
+    }
+
+    /// Matches the torrents whose total size in bytes is less than `size`.
+    pub fn size_below(self, size: u64) -> Self {
+        self.filter(Filter::SizeBelow(size))
+    }
+
+    /// Matches the torrents added more than `age` ago.
+    pub fn older_than(self, age: Duration) -> Self {
+        self.filter(Filter::AddedBefore(age))
+    }
+
+    /// Matches the torrents added at most `age` ago.
+    pub fn newer_than(self, age: Duration) -> Self {
+        self.filter(Filter::AddedWithin(age))
+    }
+
+    /// Matches the torrents whose name matches the regular expression.
+    pub fn name(self, regex: Regex) -> Self {
+        self.filter(Filter::Name(regex))
+    }
+
+    /// Matches the torrents that have an error if `error` is true,
+    /// and the ones without an error otherwise.
+    pub fn has_error(self, error: bool) -> Self {
+        self.filter(Filter::Error(error))
+    }
+
+    /// Matches the torrents that match either this query or `other`.
+    /// The sorting and the limit of `other` are ignored.
+    pub fn or(self, other: Query) -> Self {
+        Query {
+            filters: vec![Filter::Any(vec![self.to_filter(), other.to_filter()])],
+            sort: self.sort,
+            limit: self.limit
+        }
+    }
+
+    /// Matches the torrents that don't match this query.
+    pub fn not(self) -> Self {
+        Query {
+            filters: vec![Filter::Not(Box::new(self.to_filter()))],
+            sort: self.sort,
+            limit: self.limit
+        }
+    }
+
+    /// Sorts the matching torrents, in descending order if `descending` is true.
+    pub fn sort_by(mut self, key: SortKey, descending: bool) -> Self {
+        self.sort = Some((key, descending));
+        self
+    }
+
+    /// Returns at most `limit` torrents.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Combines the conditions into a single filter.
+    fn to_filter(&self) -> Filter {
+        Filter::All(self.filters.clone())
+    }
+
+    /// Returns the fields needed to evaluate the query and to sort the torrents.
+    pub fn fields(&self) -> Vec<TorrentField> {
+        let mut fields = Vec::new();
+        for filter in &self.filters {
+            filter.fields(&mut fields);
+        }
+        if let Some((key, _)) = self.sort {
+            add_field(&mut fields, key.field());
+        }
+        fields
+    }
+
+    /// Returns a request for the fields needed by the query and the id of the torrents.
+    pub fn request(&self) -> GetTorrent {
+        let mut fields = vec![TorrentField::Id];
+        for field in self.fields() {
+            add_field(&mut fields, field);
+        }
+        GetTorrent::new().fields(fields)
+    }
+
+    /// Returns true if the torrent matches the conditions.
+    pub fn matches(&self, torrent: &Torrent) -> bool {
+        combine(&self.filters, torrent, false) == Some(true)
+    }
+
+    /// Returns the matching torrents, sorted and limited.
+    pub fn apply<I: IntoIterator<Item = Torrent>>(&self, torrents: I) -> Vec<Torrent> {
+        let mut torrents: Vec<Torrent> = torrents.into_iter()
+            .filter(|torrent| self.matches(torrent))
+            .collect();
+
+        if let Some((key, descending)) = self.sort {
+            torrents.sort_by(|a, b| key.compare(a, b, descending));
+        }
+
+        if let Some(limit) = self.limit {
+            torrents.truncate(limit);
+        }
+
+        torrents
+    }
+}
@@ -84,5 +84,6 @@ impl AddTorrent {
 
 impl Request for AddTorrent {
     type Response = AddTorrentResponse;
-    fn method_name(&self) -> &'static str { "torrent-add" }
+    fn method_name(&self) -> &str { "torrent-add" }
+    fn is_idempotent(&self) -> bool { false }
 }
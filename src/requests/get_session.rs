@@ -0,0 +1,27 @@
+use responses::GetSession as GetSessionResponse;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use super::{Request, RequestArguments};
+
+/// A request to fetch the daemon's session information, including the versions of the
+/// daemon and its RPC interface.
+#[derive(Clone)]
+pub struct GetSession;
+
+impl GetSession {
+    /// Creates a request to fetch the session information.
+    pub fn new() -> GetSession {
+        GetSession
+    }
+}
+
+impl Request for GetSession {
+    type Response = GetSessionResponse;
+    fn method_name(&self) -> &str { "session-get" }
+}
+
+impl RequestArguments for GetSession {
+    fn arguments(&self) -> Value {
+        Value::Object(BTreeMap::new())
+    }
+}
@@ -1,14 +1,30 @@
 use torrent::TorrentField;
 use responses::GetTorrent as GetTorrentResponse;
-use super::Request;
+use serde_json::{self, Value};
+use std::collections::BTreeMap;
+use super::{Request, RequestArguments};
+
+/// How many seconds a torrent counts as recently active after it was active or removed.
+pub const RECENTLY_ACTIVE_SECONDS: u64 = 60;
+
+/// The format in which the daemon returns the torrents.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Format {
+    /// Each torrent is an object containing the requested fields. This is the default.
+    Objects,
+    /// The torrents are returned as a table. The first row contains the field names and
+    /// each following row contains the values of one torrent. The keys are not repeated
+    /// for every torrent which makes the response considerably smaller.
+    Table
+}
 
 /// A request to fetch information about the requested torrent(s).
-#[derive(Serialize, Clone)]
+#[derive(Clone)]
 pub struct GetTorrent {
-    #[serde(rename="ids", skip_serializing_if="Vec::is_empty")]
     _ids: Vec<u64>,
-    #[serde(rename="fields", skip_serializing_if="Vec::is_empty")]
-    _fields: Vec<TorrentField>
+    _recently_active: bool,
+    _fields: Vec<TorrentField>,
+    _format: Format
 }
 
 impl GetTorrent {
@@ -18,19 +34,38 @@ impl GetTorrent {
     pub fn new() -> GetTorrent {
         GetTorrent {
             _ids: Vec::new(),
-            _fields: Vec::new()
+            _recently_active: false,
+            _fields: Vec::new(),
+            _format: Format::Objects
+        }
+    }
+
+    /// Creates a request to fetch only those torrents that have been active within the
+    /// last `RECENTLY_ACTIVE_SECONDS` seconds. The response will also contain the ids of
+    /// the torrents removed within that time. See `responses::GetTorrent::removed`.
+    ///
+    /// The daemon doesn't track the previous requests, so changes older than that are
+    /// missed if the requests are sent less often.
+    pub fn recently_active() -> GetTorrent {
+        GetTorrent {
+            _ids: Vec::new(),
+            _recently_active: true,
+            _fields: Vec::new(),
+            _format: Format::Objects
         }
     }
 
     /// Adds an torrent which's information is to fetched by it's id.
     pub fn id(mut self, id: u64) -> Self {
         self._ids.push(id);
+        self._recently_active = false;
         self
     }
 
     /// Sets the list of those torrent's ids which are to be fetched.
     pub fn ids(mut self, ids: Vec<u64>) -> Self {
         self._ids = ids;
+        self._recently_active = false;
         self
     }
 
@@ -45,9 +80,48 @@ impl GetTorrent {
         self._fields = fields;
         self
     }
+
+    /// Sets the format in which the daemon returns the torrents.
+    /// The response is decoded into `Torrent`s regardless of the format.
+    pub fn format(mut self, format: Format) -> Self {
+        self._format = format;
+        self
+    }
 }
 
 impl Request for GetTorrent {
     type Response = GetTorrentResponse;
-    fn method_name(&self) -> &'static str { "torrent-get" }
+    fn method_name(&self) -> &str { "torrent-get" }
+
+    fn min_rpc_version(&self) -> u64 {
+        let fields = self._fields.iter().map(|f| f.min_rpc_version()).max().unwrap_or(1);
+
+        match self._format {
+            Format::Table => ::std::cmp::max(fields, 16),
+            Format::Objects => fields
+        }
+    }
+}
+
+impl RequestArguments for GetTorrent {
+    fn arguments(&self) -> Value {
+        let mut args = BTreeMap::new();
+
+        if self._recently_active {
+            args.insert("ids".to_string(), Value::String("recently-active".to_string()));
+        } else if !self._ids.is_empty() {
+            args.insert("ids".to_string(), Value::Array(self._ids.iter()
+                                                        .map(|id| Value::U64(*id)).collect()));
+        }
+
+        if !self._fields.is_empty() {
+            args.insert("fields".to_string(), serde_json::to_value(&self._fields));
+        }
+
+        if self._format == Format::Table {
+            args.insert("format".to_string(), Value::String("table".to_string()));
+        }
+
+        Value::Object(args)
+    }
 }
@@ -1,12 +1,16 @@
 mod get_torrent;
+mod get_session;
 mod add_torrent;
 mod torrent_set;
 mod torrent_action;
+mod raw;
 
-pub use self::get_torrent::GetTorrent;
+pub use self::get_torrent::{GetTorrent, Format, RECENTLY_ACTIVE_SECONDS};
+pub use self::get_session::GetSession;
 pub use self::add_torrent::AddTorrent;
 pub use self::torrent_set::TorrentSet;
 pub use self::torrent_action::{ActionType, ActionTarget, TorrentAction};
+pub use self::raw::{RawRequest, WithArguments};
 
 use serde_json::{self, Value};
 use serde::Serialize;
@@ -26,13 +30,31 @@ impl<T> RequestArguments for T where T: Serialize {
 pub trait Request: RequestArguments {
     type Response;
 
-    fn method_name(&self) -> &'static str;
-    
-    fn to_value(&self) -> Value {
+    fn method_name(&self) -> &str;
+
+    /// The oldest version of the RPC interface that supports this request.
+    fn min_rpc_version(&self) -> u64 { 1 }
+
+    /// Returns false if sending the request twice has a different effect than sending
+    /// it once. Such requests are not retried by default.
+    fn is_idempotent(&self) -> bool { true }
+
+    /// Adds an untyped argument to the request, for arguments the typed request
+    /// doesn't support yet. The name is the one used by the legacy protocol.
+    fn with_argument<K: Into<String>>(self, key: K, value: Value) -> WithArguments<Self>
+        where Self: Sized
+    {
+        WithArguments::new(self).with_argument(key, value)
+    }
+
+    /// Creates the JSON object sent to the daemon. The daemon includes the `tag`
+    /// in its response which allows matching the response to the request.
+    fn to_value(&self, tag: usize) -> Value {
         Value::Object({
             let mut obj = BTreeMap::new();
             obj.insert("method".to_string(), Value::String(self.method_name().to_string()));
             obj.insert("arguments".to_string(), self.arguments());
+            obj.insert("tag".to_string(), Value::U64(tag as u64));
             obj
         })
     }
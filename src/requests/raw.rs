@@ -0,0 +1,85 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+use super::{Request, RequestArguments};
+
+/// An untyped request for methods that have no typed request yet. The response is
+/// the `arguments` object returned by the daemon.
+///
+/// The method and the arguments use the names of the legacy protocol, e.g.
+/// `session-set` and `speed-limit-down`. They are translated if the daemon
+/// is talked to using JSON-RPC.
+#[derive(Clone, Debug)]
+pub struct RawRequest {
+    pub method: String,
+    pub arguments: Value
+}
+
+impl RawRequest {
+    /// Creates a request calling `method` with the given arguments.
+    pub fn new<S: Into<String>>(method: S, arguments: Value) -> RawRequest {
+        RawRequest {
+            method: method.into(),
+            arguments: arguments
+        }
+    }
+}
+
+impl Request for RawRequest {
+    type Response = Value;
+
+    fn method_name(&self) -> &str { &self.method }
+
+    /// Only the methods that read the daemon's state are considered idempotent.
+    fn is_idempotent(&self) -> bool {
+        ::interceptor::is_read_only(&self.method)
+    }
+}
+
+impl RequestArguments for RawRequest {
+    fn arguments(&self) -> Value {
+        self.arguments.clone()
+    }
+}
+
+/// A typed request with additional untyped arguments. Created using `Request::with_argument`.
+#[derive(Clone, Debug)]
+pub struct WithArguments<R> {
+    request: R,
+    extra: BTreeMap<String, Value>
+}
+
+impl<R: Request> WithArguments<R> {
+    /// Wraps the request without adding any arguments.
+    pub fn new(request: R) -> WithArguments<R> {
+        WithArguments {
+            request: request,
+            extra: BTreeMap::new()
+        }
+    }
+
+    /// Adds an argument to the request. Replaces the argument set by the typed request
+    /// if they have the same name.
+    pub fn with_argument<K: Into<String>>(mut self, key: K, value: Value) -> Self {
+        self.extra.insert(key.into(), value);
+        self
+    }
+}
+
+impl<R: Request> Request for WithArguments<R> {
+    type Response = R::Response;
+
+    fn method_name(&self) -> &str { self.request.method_name() }
+    fn min_rpc_version(&self) -> u64 { self.request.min_rpc_version() }
+    fn is_idempotent(&self) -> bool { self.request.is_idempotent() }
+}
+
+impl<R: Request> RequestArguments for WithArguments<R> {
+    fn arguments(&self) -> Value {
+        let mut args = match self.request.arguments() {
+            Value::Object(obj) => obj,
+            _ => BTreeMap::new()
+        };
+        args.extend(self.extra.clone());
+        Value::Object(args)
+    }
+}
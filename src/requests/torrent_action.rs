@@ -33,7 +33,7 @@ impl TorrentAction {
 impl Request for TorrentAction {
     type Response = ::responses::TorrentAction;
 
-    fn method_name(&self) -> &'static str {
+    fn method_name(&self) -> &str {
         match self.typ {
             ActionType::Start => "torrent-start",
             ActionType::Stop => "torrent-stop",
@@ -41,6 +41,13 @@ impl Request for TorrentAction {
             ActionType::Reannounce => "torrent-reannounce"
         }
     }
+
+    fn min_rpc_version(&self) -> u64 {
+        match self.typ {
+            ActionType::Reannounce => 5,
+            _ => 1
+        }
+    }
 }
 
 impl RequestArguments for TorrentAction {
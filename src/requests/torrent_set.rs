@@ -63,7 +63,7 @@ pub struct TorrentSetResponse;
 impl Request for TorrentSet {
     type Response = TorrentSetResponse;
 
-    fn method_name(&self) -> &'static str { "torrent-set" }
+    fn method_name(&self) -> &str { "torrent-set" }
 }
 
 impl RequestArguments for TorrentSet {
@@ -18,7 +18,8 @@ pub struct AddTorrent {
 
 impl Response for AddTorrent {
     fn from_value(value: Value) -> Result<AddTorrent> {
-        let obj = value.as_object().unwrap();
+        let obj = value.as_object()
+            .ok_or(Error::InvalidType("object".to_string(), "arguments".to_string()))?;
 
         let mut was_duplicate = false;
         let info: Value;
@@ -28,7 +29,7 @@ impl Response for AddTorrent {
             info = i.clone();
             was_duplicate = true;
         } else {
-            bail!(ErrorKind::MissingField("torrent-added".to_string()))
+            return Err(Error::MissingField("torrent-added".to_string()))
         }
 
         let field = match was_duplicate {
@@ -37,22 +38,22 @@ impl Response for AddTorrent {
         };
         
         let info = info.as_object()
-            .ok_or(ErrorKind::InvalidType("object".to_string(), field.to_string()))?;
+            .ok_or(Error::InvalidType("object".to_string(), field.to_string()))?;
 
         Ok(AddTorrent {
             was_duplicate: was_duplicate,
             id: try!(info.get("id")
-                .ok_or(ErrorKind::MissingField("id".to_string()))
+                .ok_or(Error::MissingField("id".to_string()))
                 .and_then(|v| v.as_u64()
-                          .ok_or(ErrorKind::InvalidType("number".to_string(), "id".to_string())))),
+                          .ok_or(Error::InvalidType("number".to_string(), "id".to_string())))),
             name: try!(info.get("name")
-                       .ok_or(ErrorKind::MissingField("name".to_string()))
+                       .ok_or(Error::MissingField("name".to_string()))
                        .and_then(|v| v.as_str()
-                                 .ok_or(ErrorKind::InvalidType("string".to_string(), "name".to_string())))).to_string(),
+                                 .ok_or(Error::InvalidType("string".to_string(), "name".to_string())))).to_string(),
             hash_string: try!(info.get("hashString")
-                              .ok_or(ErrorKind::MissingField("hashString".to_string()))
+                              .ok_or(Error::MissingField("hashString".to_string()))
                               .and_then(|v| v.as_str()
-                                        .ok_or(ErrorKind::InvalidType("string".to_string(), "hashString".to_string())))).to_string()
+                                        .ok_or(Error::InvalidType("string".to_string(), "hashString".to_string())))).to_string()
         })
     }
 }
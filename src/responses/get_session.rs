@@ -0,0 +1,12 @@
+/// A response to the request `GetSession`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetSession {
+    /// The current version of the RPC interface.
+    #[serde(rename="rpc-version")]
+    pub rpc_version: u64,
+    /// The oldest version of the RPC interface the daemon is compatible with.
+    #[serde(rename="rpc-version-minimum")]
+    pub rpc_version_minimum: u64,
+    /// The version of the daemon, e.g. "2.92 (14714)".
+    pub version: String
+}
@@ -1,11 +1,90 @@
+use super::Response;
+use error::deserialize::*;
 use torrent::Torrent;
+use serde_json::{self, Value};
+use std::collections::BTreeMap;
 use std::iter::IntoIterator;
 use std::vec;
 
 /// A response to the request `GetTorrent`
-#[derive(Deserialize, Debug)]
+#[derive(Debug)]
 pub struct GetTorrent {
-    torrents: Vec<Torrent>
+    torrents: Vec<Torrent>,
+    removed: Vec<u64>
+}
+
+impl GetTorrent {
+    /// Returns the ids of the torrents that have been removed within the last
+    /// `RECENTLY_ACTIVE_SECONDS` seconds of a `recently-active` request.
+    /// Always empty for other kinds of requests.
+    pub fn removed(&self) -> &[u64] {
+        &self.removed
+    }
+}
+
+/// Decodes the torrents returned using the table format. The first row contains
+/// the names of the fields and the rest of the rows contain the values.
+fn from_table(mut rows: Vec<Value>) -> Result<Vec<Torrent>> {
+    if rows.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let header = match rows.remove(0) {
+        Value::Array(keys) => keys,
+        _ => return Err(Error::InvalidType("array".to_string(), "torrents".to_string()))
+    };
+
+    let mut keys = Vec::with_capacity(header.len());
+    for key in header {
+        match key {
+            Value::String(key) => keys.push(key),
+            _ => return Err(Error::InvalidType("string".to_string(), "torrents".to_string()))
+        }
+    }
+
+    let mut torrents = Vec::with_capacity(rows.len());
+    for row in rows {
+        let values = match row {
+            Value::Array(values) => values,
+            _ => return Err(Error::InvalidType("array".to_string(), "torrents".to_string()))
+        };
+
+        let obj: BTreeMap<String, Value> = keys.iter().cloned().zip(values).collect();
+        torrents.push(serde_json::from_value(Value::Object(obj))?);
+    }
+
+    Ok(torrents)
+}
+
+impl Response for GetTorrent {
+    fn from_value(value: Value) -> Result<GetTorrent> {
+        let mut obj = match value {
+            Value::Object(obj) => obj,
+            _ => return Err(Error::InvalidType("object".to_string(), "arguments".to_string()))
+        };
+
+        let rows = match obj.remove("torrents") {
+            Some(Value::Array(rows)) => rows,
+            Some(_) => return Err(Error::InvalidType("array".to_string(), "torrents".to_string())),
+            None => return Err(Error::MissingField("torrents".to_string()))
+        };
+
+        // In the table format every row is an array instead of an object.
+        let torrents = match rows.first() {
+            Some(&Value::Array(_)) => from_table(rows)?,
+            _ => serde_json::from_value(Value::Array(rows))?
+        };
+
+        let removed = match obj.remove("removed") {
+            Some(removed) => serde_json::from_value(removed)?,
+            None => Vec::new()
+        };
+
+        Ok(GetTorrent {
+            torrents: torrents,
+            removed: removed
+        })
+    }
 }
 
 impl IntoIterator for GetTorrent {
@@ -0,0 +1,120 @@
+use error::*;
+use rand;
+use std::cmp;
+use std::time::Duration;
+
+/// A kind of failure that can be retried.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RetryOn {
+    /// Connecting to the daemon or transferring the request or the response failed.
+    Connection,
+    /// The daemon responded with a 5xx status code.
+    ServerError,
+    /// The daemon did not respond in time.
+    Timeout
+}
+
+/// Decides which failed requests are sent again and how long to wait before that.
+///
+/// The delay before each retry grows exponentially from the initial backoff up to
+/// the maximum backoff, and a random part of it is removed to avoid many clients
+/// retrying at the same time.
+///
+/// Requests that are not idempotent, such as `torrent-add`, are not retried unless
+/// explicitly allowed, because the daemon may have processed the first attempt even
+/// if the client never received the response.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    jitter: f64,
+    retry_on: Vec<RetryOn>,
+    retry_non_idempotent: bool
+}
+
+impl RetryPolicy {
+    /// Creates a policy that makes up to 3 attempts, waits from 100 milliseconds up to
+    /// 10 seconds between them and retries connection failures, server errors and timeouts.
+    pub fn new() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            jitter: 0.5,
+            retry_on: vec![RetryOn::Connection, RetryOn::ServerError, RetryOn::Timeout],
+            retry_non_idempotent: false
+        }
+    }
+
+    /// Creates a policy that never retries. This is the default.
+    pub fn none() -> RetryPolicy {
+        RetryPolicy::new().max_attempts(1)
+    }
+
+    /// Sets the maximum number of attempts, including the first one.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = cmp::max(max_attempts, 1);
+        self
+    }
+
+    /// Sets the delay before the first retry and the maximum delay between retries.
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Sets the largest fraction of the delay that is randomly removed, between 0 and 1.
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.max(0.0).min(1.0);
+        self
+    }
+
+    /// Sets the kinds of failures that are retried.
+    pub fn retry_on(mut self, retry_on: Vec<RetryOn>) -> Self {
+        self.retry_on = retry_on;
+        self
+    }
+
+    /// Allows retrying requests that are not idempotent.
+    pub fn retry_non_idempotent(mut self, retry: bool) -> Self {
+        self.retry_non_idempotent = retry;
+        self
+    }
+
+    /// Returns the delay before the given retry, counting from 1.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        let delay = self.initial_backoff.checked_mul(factor).unwrap_or(self.max_backoff);
+        let delay = cmp::min(delay, self.max_backoff);
+
+        let jitter = 1.0 - self.jitter * rand::random::<f64>();
+        let nanos = (delay.as_secs() as f64 * 1e9 + delay.subsec_nanos() as f64) * jitter;
+        Duration::new((nanos / 1e9) as u64, (nanos % 1e9) as u32)
+    }
+
+    /// Returns true if a request that failed with the error on the given attempt,
+    /// counting from 1, should be sent again.
+    pub fn should_retry(&self, error: &Error, attempt: u32, idempotent: bool) -> bool {
+        if attempt >= self.max_attempts || !(idempotent || self.retry_non_idempotent) {
+            return false;
+        }
+
+        match classify(error) {
+            Some(kind) => self.retry_on.contains(&kind),
+            None => false
+        }
+    }
+}
+
+/// Returns the kind of a retryable error.
+fn classify(error: &Error) -> Option<RetryOn> {
+    match *error.root() {
+        Error::Transport(transport::Error::Http(_)) | Error::Io(_) => Some(RetryOn::Connection),
+        Error::Transport(transport::Error::Timeout) => Some(RetryOn::Timeout),
+        Error::Daemon(daemon::Error::StatusCode(status)) if status.is_server_error() =>
+            Some(RetryOn::ServerError),
+        _ => None
+    }
+}
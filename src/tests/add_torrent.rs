@@ -1,5 +1,7 @@
 use super::create_transmission;
 use std::io::Cursor;
+use error::Error;
+use error::daemon::MessageKind;
 use requests::AddTorrent;
 use torrent::TorrentField;
 
@@ -7,7 +9,7 @@ use torrent::TorrentField;
 pub fn from_file() {
     println!("{}", TorrentField::all()[0]);
     
-    let mut tr = create_transmission();
+    let tr = create_transmission();
 
     let data = include_bytes!("./dummy.torrent");
     let mut torrent = Cursor::new(&data[..]);
@@ -16,3 +18,18 @@ pub fn from_file() {
     
     let res = tr.send(&req).expect("Error while communicating with the server!");
 }
+
+#[test]
+pub fn invalid_torrent() {
+    let tr = create_transmission();
+
+    let mut torrent = Cursor::new(&b"not a torrent"[..]);
+    let req = AddTorrent::from_reader(&mut torrent).expect("Error while creating the request!");
+
+    let err = tr.send(&req).err().expect("The daemon accepted an invalid torrent!");
+    match *err.root() {
+        Error::Daemon(ref e) => assert_eq!(e.message_kind(), Some(MessageKind::InvalidTorrent)),
+        ref err => panic!("Unexpected error: {:?}", err)
+    }
+    assert_eq!(err.context().map(|c| &*c.method), Some("torrent-add"));
+}
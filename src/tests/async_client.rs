@@ -0,0 +1,123 @@
+use AsyncTransmission;
+use error::{Error, protocol};
+use fake::FakeDaemon;
+use futures::Future;
+use futures::sync::oneshot;
+use reqwest::Url;
+use requests::GetTorrent;
+use std::net::TcpListener;
+use std::thread;
+use super::respond;
+use tokio::runtime::current_thread::Runtime;
+use tokio::runtime::Runtime as ThreadPoolRuntime;
+use torrent::TorrentField;
+
+#[test]
+fn send() {
+    let server = FakeDaemon::new()
+        .fixture_str(include_str!("fixtures/torrents.json"))
+        .expect("Failed to read the fixture.")
+        .start("127.0.0.1:0")
+        .expect("Failed to start the fake daemon.");
+
+    let mut tr = AsyncTransmission::new();
+    tr.set_url(Url::parse(server.url().as_str()).unwrap());
+
+    let mut runtime = Runtime::new().expect("Failed to start the runtime.");
+    let res = runtime.block_on(tr.send(&GetTorrent::new().field(TorrentField::Id)))
+        .expect("Error while communicating with the server.");
+    assert_eq!(res.into_iter().count(), 3);
+}
+
+#[test]
+fn missing_session_id() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/transmission/rpc", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        respond(stream, b"HTTP/1.1 409 Conflict\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+    });
+
+    let mut tr = AsyncTransmission::new();
+    tr.set_url(Url::parse(&url).unwrap());
+
+    let mut runtime = Runtime::new().expect("Failed to start the runtime.");
+    let err = runtime.block_on(tr.send(&GetTorrent::new()))
+        .err().expect("The request succeeded without a session id.");
+    match *err.root() {
+        Error::Protocol(protocol::Error::MissingSessionId) => (),
+        ref err => panic!("Unexpected error: {:?}", err)
+    }
+}
+
+#[test]
+fn spawn_on_thread_pool() {
+    let server = FakeDaemon::new()
+        .fixture_str(include_str!("fixtures/torrents.json"))
+        .expect("Failed to read the fixture.")
+        .start("127.0.0.1:0")
+        .expect("Failed to start the fake daemon.");
+
+    let mut tr = AsyncTransmission::new();
+    tr.set_url(Url::parse(server.url().as_str()).unwrap());
+
+    // The future is `Send + 'static`, so it can outlive the client on another thread.
+    let (sender, receiver) = oneshot::channel();
+    let future = tr.send(&GetTorrent::new().field(TorrentField::Id));
+    drop(tr);
+
+    let mut runtime = ThreadPoolRuntime::new().expect("Failed to start the runtime.");
+    runtime.spawn(future.then(move |result| {
+        let _ = sender.send(result.map(|res| res.into_iter().count()));
+        Ok(())
+    }));
+
+    let count = receiver.wait().expect("The spawned request was dropped.")
+        .expect("Error while communicating with the server.");
+    assert_eq!(count, 3);
+    runtime.shutdown_on_idle().wait().unwrap();
+}
+
+#[test]
+fn session_conflict() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/transmission/rpc", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for stream in listener.incoming().take(4) {
+            respond(stream.unwrap(), b"HTTP/1.1 409 Conflict\r\nX-Transmission-Session-Id: stale\r\n\
+                                      Content-Length: 0\r\nConnection: close\r\n\r\n");
+        }
+    });
+
+    let mut tr = AsyncTransmission::new();
+    tr.set_url(Url::parse(&url).unwrap());
+
+    let mut runtime = Runtime::new().expect("Failed to start the runtime.");
+    let err = runtime.block_on(tr.send(&GetTorrent::new()))
+        .err().expect("The request succeeded with a rejected session id.");
+    match *err.root() {
+        Error::Protocol(protocol::Error::SessionConflict(3)) => (),
+        ref err => panic!("Unexpected error: {:?}", err)
+    }
+}
+
+#[test]
+fn only_200_is_success() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/transmission/rpc", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        respond(stream, b"HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n");
+    });
+
+    let mut tr = AsyncTransmission::new();
+    tr.set_url(Url::parse(&url).unwrap());
+
+    let mut runtime = Runtime::new().expect("Failed to start the runtime.");
+    let err = runtime.block_on(tr.send(&GetTorrent::new()))
+        .err().expect("The request succeeded with 204.");
+    match *err.root() {
+        Error::Daemon(_) => (),
+        ref err => panic!("Unexpected error: {:?}", err)
+    }
+}
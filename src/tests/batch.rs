@@ -0,0 +1,87 @@
+use Transmission;
+use error::Result;
+use hyper::Url;
+use hyper::header::Headers;
+use hyper::status::StatusCode;
+use requests::{GetSession, GetTorrent};
+use serde_json::{self, Value};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+use super::create_fake_transmission;
+use torrent::TorrentField;
+use transport::{Transport, TransportResponse};
+
+#[test]
+fn tuple() {
+    let tr = create_fake_transmission();
+
+    let (session, torrents) = tr.send_batch(&(GetSession::new(), GetTorrent::new()));
+    assert!(session.expect("session-get failed.").rpc_version >= 1);
+    assert_eq!(torrents.expect("torrent-get failed.").into_iter().count(), 3);
+}
+
+#[test]
+fn same_type() {
+    let tr = create_fake_transmission();
+
+    let requests = vec![GetTorrent::new().id(1).field(TorrentField::Id),
+                        GetTorrent::new().id(2).field(TorrentField::Id)];
+    let ids: Vec<_> = tr.send_batch(&requests).into_iter()
+        .map(|res| res.expect("torrent-get failed.").into_iter().next().and_then(|t| t.id))
+        .collect();
+
+    assert_eq!(ids, vec![Some(1), Some(2)]);
+}
+
+/// A transport that responds slowly and counts the requests in flight.
+struct SlowTransport {
+    in_flight: AtomicUsize,
+    max_in_flight: Arc<AtomicUsize>
+}
+
+impl Transport for SlowTransport {
+    fn post(&self, _url: &Url, _headers: Headers, body: &[u8]) -> Result<TransportResponse> {
+        let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        let mut max = self.max_in_flight.load(Ordering::SeqCst);
+        while in_flight > max {
+            let previous = self.max_in_flight.compare_and_swap(max, in_flight, Ordering::SeqCst);
+            if previous == max {
+                break;
+            }
+            max = previous;
+        }
+
+        thread::sleep(Duration::from_millis(10));
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+        // Echo the tag like the daemon does.
+        let request: Value = serde_json::from_slice(body)?;
+        let tag = request.find("tag").and_then(|t| t.as_u64()).unwrap_or(0);
+        Ok(TransportResponse {
+            status: StatusCode::Ok,
+            headers: Headers::new(),
+            body: format!(r#"{{"result":"success","arguments":{{"torrents":[]}},"tag":{}}}"#, tag).into_bytes()
+        })
+    }
+}
+
+#[test]
+fn limit() {
+    let max_in_flight = Arc::new(AtomicUsize::new(0));
+    let mut tr = Transmission::new();
+    tr.set_transport(SlowTransport {
+        in_flight: AtomicUsize::new(0),
+        max_in_flight: max_in_flight.clone()
+    });
+    tr.set_batch_limit(3);
+
+    let requests = vec![GetTorrent::new(); 20];
+    let results = tr.send_batch(&requests);
+
+    assert_eq!(results.len(), 20);
+    assert!(results.iter().all(|res| res.is_ok()));
+    let max = max_in_flight.load(Ordering::SeqCst);
+    assert!(max >= 1 && max <= 3, "{} requests were sent at the same time.", max);
+}
@@ -0,0 +1,62 @@
+use error::daemon::{self, from_status};
+use hyper::status::StatusCode;
+
+// The pages below are the ones Transmission's RPC server sends with these status codes.
+
+#[test]
+fn unauthorized() {
+    let body = b"<html><head><title>401: Unauthorized</title></head>\
+                 <body><h1>401: Unauthorized</h1>Unauthorized User</body></html>";
+    match from_status(StatusCode::Unauthorized, body) {
+        daemon::Error::Unauthorized(ref explanation) => assert_eq!(explanation, "Unauthorized User"),
+        err => panic!("Unexpected error: {:?}", err)
+    }
+}
+
+#[test]
+fn ip_whitelist() {
+    let body = b"<html><head><title>403: Forbidden</title></head><body><h1>403: Forbidden</h1>\
+                 <p>Unauthorized IP Address.</p>\
+                 <p>Either disable the IP address whitelist or add your address to it.</p>\
+                 <p>If you're editing settings.json, see the 'rpc-whitelist' and 'rpc-whitelist-enabled' entries.</p>\
+                 <p>If you're still using ACLs, use a whitelist instead. See the transmission-daemon manpage for details.</p>\
+                 </body></html>";
+    match from_status(StatusCode::Forbidden, body) {
+        daemon::Error::IpWhitelist(ref explanation) => assert_eq!(explanation,
+            "Unauthorized IP Address. Either disable the IP address whitelist or add your address to it. \
+             If you're editing settings.json, see the 'rpc-whitelist' and 'rpc-whitelist-enabled' entries. \
+             If you're still using ACLs, use a whitelist instead. See the transmission-daemon manpage for details."),
+        err => panic!("Unexpected error: {:?}", err)
+    }
+}
+
+#[test]
+fn host_whitelist() {
+    let body = b"<html><head><title>421: Misdirected Request</title></head><body><h1>421: Misdirected Request</h1>\
+                 <p>Transmission received your request, but the hostname was unrecognized.</p>\
+                 <p>To fix this, choose one of the following options:\
+                 <ul><li>Enable authentication in Transmission settings.</li>\
+                 <li>Add the hostname to the RPC whitelist setting.</li></ul></p>\
+                 <p>If you're editing settings.json, see the 'rpc-host-whitelist' and \
+                 'rpc-host-whitelist-enabled' entries.</p>\
+                 <p>This requirement has been added to help prevent \
+                 <a href=\"https://en.wikipedia.org/wiki/DNS_rebinding\">DNS Rebinding</a> attacks.</p>\
+                 </body></html>";
+    match from_status(StatusCode::from_u16(421), body) {
+        daemon::Error::HostWhitelist(ref explanation) => assert_eq!(explanation,
+            "Transmission received your request, but the hostname was unrecognized. \
+             To fix this, choose one of the following options: \
+             Enable authentication in Transmission settings. Add the hostname to the RPC whitelist setting. \
+             If you're editing settings.json, see the 'rpc-host-whitelist' and 'rpc-host-whitelist-enabled' entries. \
+             This requirement has been added to help prevent DNS Rebinding attacks."),
+        err => panic!("Unexpected error: {:?}", err)
+    }
+}
+
+#[test]
+fn other_status() {
+    match from_status(StatusCode::InternalServerError, b"<h1>500: Internal Server Error</h1>") {
+        daemon::Error::StatusCode(StatusCode::InternalServerError) => (),
+        err => panic!("Unexpected error: {:?}", err)
+    }
+}
@@ -0,0 +1,32 @@
+use super::{create_transmission, create_fake_transmission};
+use requests::{GetTorrent, Format};
+use torrent::TorrentField;
+
+#[test]
+fn negotiate() {
+    let tr = create_transmission();
+    assert!(tr.capabilities().is_none());
+
+    let rpc_version = tr.negotiate().expect("Error while communicating with the server.").rpc_version;
+    assert!(rpc_version >= 1);
+
+    let req = GetTorrent::new()
+        .field(TorrentField::Id)
+        .format(Format::Table);
+
+    match tr.send(&req) {
+        Ok(_) => assert!(rpc_version >= 16),
+        Err(_) => assert!(rpc_version < 16)
+    }
+}
+
+#[test]
+fn renegotiate_after_url_change() {
+    let mut tr = create_fake_transmission();
+    tr.negotiate().expect("Error while communicating with the server.");
+    assert!(tr.capabilities().is_some());
+
+    let url = create_fake_transmission().url.clone();
+    tr.set_url(url);
+    assert!(tr.capabilities().is_none());
+}
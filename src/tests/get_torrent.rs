@@ -1,10 +1,13 @@
-use super::create_transmission;
-use requests::GetTorrent;
+use super::{create_transmission, create_fake_transmission};
+use requests::{GetTorrent, Format, RawRequest, RequestArguments};
+use responses::{Response, GetTorrent as GetTorrentResponse};
+use serde_json::{self, Value};
+use std::collections::BTreeMap;
 use torrent::TorrentField;
 
 #[test]
 fn get_all() {
-    let mut tr = create_transmission();
+    let tr = create_transmission();
 
     // Fetch the values of one specific value for all torrents.
     let req = GetTorrent::new()
@@ -33,3 +36,50 @@ fn get_all() {
     assert!(second.name.is_some());
     assert!(second.name.unwrap() == first.name.unwrap());
 }
+
+#[test]
+fn recently_active() {
+    let req = GetTorrent::recently_active().field(TorrentField::Id);
+    let args = req.arguments();
+
+    assert_eq!(args.find("ids").and_then(|v| v.as_str()), Some("recently-active"));
+
+    let tr = create_fake_transmission();
+    let mut args = BTreeMap::new();
+    args.insert("ids".to_string(), Value::Array(vec![Value::U64(2)]));
+    tr.send(&RawRequest::new("torrent-remove", Value::Object(args)))
+        .expect("Error while communicating with the server.");
+
+    let res = tr.send(&req).expect("Error while communicating with the server.");
+    assert_eq!(res.removed(), &[2][..]);
+
+    // The removal is reported to every request within the window.
+    let res = tr.send(&req).expect("Error while communicating with the server.");
+    assert_eq!(res.removed(), &[2][..]);
+}
+
+#[test]
+fn table_format() {
+    let value = serde_json::from_str(r#"{
+        "torrents": [
+            ["id", "name"],
+            [1, "first"],
+            [2, "second"]
+        ]
+    }"#).unwrap();
+
+    let res = GetTorrentResponse::from_value(value).expect("Failed to decode the table.");
+    let torrents: Vec<_> = res.into_iter().collect();
+
+    assert_eq!(torrents.len(), 2);
+    assert_eq!(torrents[1].id, Some(2));
+    assert_eq!(torrents[1].name, Some("second".to_string()));
+    assert!(torrents[1].status.is_none());
+
+    let tr = create_transmission();
+    let req = GetTorrent::new()
+        .fields(vec![TorrentField::Id, TorrentField::Name])
+        .format(Format::Table);
+
+    tr.send(&req).expect("Error while communicating with the server.");
+}
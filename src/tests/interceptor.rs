@@ -0,0 +1,104 @@
+use super::create_fake_transmission;
+use error::{Error, Result, deserialize};
+use interceptor::{CallInfo, DryRun, Interceptor, Logger, Timing};
+use requests::{TorrentAction, ActionType, ActionTarget, AddTorrent, GetTorrent, RawRequest};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use torrent::TorrentField;
+use types::Status;
+
+#[test]
+pub fn dry_run() {
+    let mut tr = create_fake_transmission();
+
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let recorded = calls.clone();
+    tr.add_interceptor(Timing::new(move |info, _, ok| {
+        recorded.lock().unwrap().push((info.method.to_string(), ok));
+    }));
+    tr.add_interceptor(DryRun);
+
+    let err = tr.send(&TorrentAction::new(ActionType::Stop, ActionTarget::All))
+        .err().expect("The request was sent in a dry run.");
+    assert!(err.is_dry_run());
+
+    let torrents = tr.send(&GetTorrent::new().fields(vec![TorrentField::Id, TorrentField::Status]))
+        .expect("Error while communicating with the server.");
+    for torrent in torrents {
+        if let Some(Status::Stopped) = torrent.status {
+            assert_eq!(torrent.id, Some(3), "A torrent was stopped in a dry run.");
+        }
+    }
+
+    assert_eq!(*calls.lock().unwrap(), vec![("torrent-stop".to_string(), false),
+                                            ("torrent-get".to_string(), true)]);
+}
+
+/// Answers every request with empty arguments and records whether the
+/// results seen after the requests were errors.
+struct EmptyResponse {
+    errors: Arc<Mutex<Vec<bool>>>
+}
+
+impl Interceptor for EmptyResponse {
+    fn before(&self, _info: &CallInfo, _request: &mut Value) -> Option<Result<Value>> {
+        Some(Ok(Value::Object(BTreeMap::new())))
+    }
+
+    fn after(&self, _info: &CallInfo, result: ::std::result::Result<&Value, &Error>) {
+        self.errors.lock().unwrap().push(result.is_err());
+    }
+}
+
+#[test]
+pub fn deserialization_error() {
+    let mut tr = create_fake_transmission();
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    tr.add_interceptor(EmptyResponse { errors: errors.clone() });
+
+    // `torrent-add` responds with the added torrent, so empty arguments fail to deserialize.
+    let err = tr.send(&AddTorrent::from_source("http://example.com/ubuntu.torrent"))
+        .err().expect("Empty arguments were accepted.");
+    match *err.root() {
+        Error::Deserialize(deserialize::Error::MissingField(_)) => (),
+        ref err => panic!("Unexpected error: {:?}", err)
+    }
+
+    assert_eq!(*errors.lock().unwrap(), vec![true]);
+}
+
+/// A writer whose output can be read while the logger owns it.
+#[derive(Clone)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+pub fn logger_redacts() {
+    let buffer = SharedBuffer(Arc::new(Mutex::new(Vec::new())));
+    let mut tr = create_fake_transmission();
+    tr.add_interceptor(Logger::new(buffer.clone()))
+      .add_interceptor(DryRun);
+
+    let mut args = BTreeMap::new();
+    args.insert("filename".to_string(), Value::String("http://example.com/a.torrent".to_string()));
+    args.insert("cookies".to_string(), Value::String("sid=cookie-secret".to_string()));
+    args.insert("proxy-password".to_string(), Value::String("password-secret".to_string()));
+    let err = tr.send(&RawRequest::new("torrent-add", Value::Object(args)))
+        .err().expect("The dry run sent the request.");
+    assert!(err.is_dry_run());
+
+    let log = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+    assert!(log.contains("a.torrent"), "{}", log);
+    assert!(!log.contains("secret"), "{}", log);
+}
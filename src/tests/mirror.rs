@@ -0,0 +1,48 @@
+use super::create_fake_transmission;
+use mirror::{Mirror, Change};
+use requests::{TorrentAction, ActionType, ActionTarget, RawRequest};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use torrent::TorrentField;
+use types::Status;
+
+#[test]
+fn sync() {
+    let tr = create_fake_transmission();
+    let mut mirror = Mirror::new().fields(vec![TorrentField::Name, TorrentField::Status]);
+    let updates = mirror.subscribe();
+
+    assert_eq!(mirror.sync(&tr).expect("Error while communicating with the server."), 1);
+    assert_eq!(mirror.torrents().len(), 3);
+
+    tr.send(&TorrentAction::new(ActionType::Stop, ActionTarget::Single { id: 1 })).unwrap();
+
+    let mut args = BTreeMap::new();
+    args.insert("ids".to_string(), Value::Array(vec![Value::U64(2)]));
+    tr.send(&RawRequest::new("torrent-remove", Value::Object(args))).unwrap();
+
+    assert_eq!(mirror.sync(&tr).expect("Error while communicating with the server."), 2);
+    assert!(mirror.get(2).is_none());
+    match mirror.get(1).and_then(|t| t.status) {
+        Some(Status::Stopped) => (),
+        status => panic!("Unexpected status: {:?}", status)
+    }
+
+    let update = updates.try_iter().last().expect("No update was sent.");
+    assert_eq!(update.revision, 2);
+    assert!(update.changes.contains(&Change::Removed(2)));
+    assert!(update.changes.contains(&Change::Updated(1)));
+}
+
+#[test]
+fn default_fields() {
+    let tr = create_fake_transmission();
+    let mut mirror = Mirror::new();
+    mirror.sync(&tr).expect("Error while communicating with the server.");
+
+    let torrent = mirror.get(1).expect("The torrent is missing.");
+    assert!(torrent.name.is_some());
+    assert!(torrent.percent_done.is_some());
+    assert!(torrent.files.is_none());
+    assert!(torrent.labels.is_none());
+}
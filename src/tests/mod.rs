@@ -1,15 +1,59 @@
 #[cfg(test)]
 pub mod add_torrent;
 
+#[cfg(all(test, feature = "async"))]
+pub mod async_client;
+
+#[cfg(test)]
+pub mod batch;
+
+#[cfg(test)]
+pub mod error;
+
 #[cfg(test)]
 pub mod get_torrent;
 
+#[cfg(test)]
+pub mod get_session;
+
+#[cfg(test)]
+pub mod interceptor;
+
+#[cfg(test)]
+pub mod mirror;
+
+#[cfg(test)]
+pub mod protocol;
+
+#[cfg(test)]
+pub mod query;
+
+#[cfg(test)]
+pub mod raw;
+
+#[cfg(test)]
+pub mod retry;
+
 #[cfg(test)]
 pub mod torrent_action;
 
+#[cfg(test)]
+pub mod trace;
+
+#[cfg(test)]
+pub mod transport;
+
+#[cfg(test)]
+pub mod watcher;
+
 use super::Transmission;
+use fake::FakeDaemon;
 use hyper::Url;
+use std::io::{BufRead, BufReader, Read, Write};
 
+/// Creates a client for the daemon given in `TR_URL` and `TR_AUTH`.
+/// If `TR_URL` is not set, a fake daemon seeded from `fixtures/torrents.json`
+/// is started for the test.
 fn create_transmission() -> Transmission {
     let mut tr = Transmission::new();
 
@@ -23,7 +67,59 @@ fn create_transmission() -> Transmission {
     let url = option_env!("TR_URL");
     if let Some(url) = url {
         tr.set_url(Url::parse(url).expect("Failed to parse the RCP URL."));
+    } else {
+        let mut daemon = FakeDaemon::new()
+            .fixture_str(include_str!("fixtures/torrents.json"))
+            .expect("Failed to read the fixture.");
+
+        if let Some(auth) = auth {
+            let mut auth = auth.split(':');
+            daemon = daemon.auth(auth.next().unwrap(), auth.next().unwrap());
+        }
+
+        let server = daemon.start("127.0.0.1:0").expect("Failed to start the fake daemon.");
+        tr.set_url(server.url());
     }
 
     tr
 }
+
+/// Creates a client for a fake daemon seeded from `fixtures/torrents.json` even if
+/// `TR_URL` is set. Used by the tests that modify the torrents or expect the fixture's.
+fn create_fake_transmission() -> Transmission {
+    let server = FakeDaemon::new()
+        .fixture_str(include_str!("fixtures/torrents.json"))
+        .expect("Failed to read the fixture.")
+        .start("127.0.0.1:0")
+        .expect("Failed to start the fake daemon.");
+
+    let mut tr = Transmission::new();
+    tr.set_url(server.url());
+    tr
+}
+
+/// Reads an HTTP request from the stream and writes the raw response.
+/// Returns the request line and the headers.
+fn respond<S: Read + Write>(mut stream: S, response: &[u8]) -> String {
+    let mut head = String::new();
+    {
+        let mut reader = BufReader::new(&mut stream);
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                break;
+            }
+            if line.to_lowercase().starts_with("content-length:") {
+                length = line[15..].trim().parse().unwrap_or(0);
+            }
+            head.push_str(&line);
+        }
+
+        let mut body = vec![0; length];
+        let _ = reader.read_exact(&mut body);
+    }
+
+    let _ = stream.write_all(response);
+    head
+}
@@ -0,0 +1,113 @@
+use error::{Error, daemon};
+use error::daemon::{JsonRpcCode, MessageKind};
+use protocol::Protocol;
+use requests::{GetTorrent, RawRequest};
+use serde_json::{self, Value};
+use torrent::TorrentField;
+
+fn json(s: &str) -> Value {
+    serde_json::from_str(s).expect("Invalid JSON in the test.")
+}
+
+fn strings(value: Option<&Value>) -> Vec<&str> {
+    value.and_then(|v| v.as_array())
+        .map(|values| values.iter().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default()
+}
+
+#[test]
+fn encode() {
+    let req = GetTorrent::new().fields(vec![TorrentField::Id, TorrentField::HashString]);
+
+    let legacy = Protocol::Legacy.encode(&req, 3);
+    assert_eq!(legacy.find("method").and_then(|m| m.as_str()), Some("torrent-get"));
+    assert_eq!(legacy.find("tag").and_then(|t| t.as_u64()), Some(3));
+    assert_eq!(strings(legacy.lookup("arguments.fields")), vec!["id", "hashString"]);
+
+    let json_rpc = Protocol::JsonRpc.encode(&req, 3);
+    assert_eq!(json_rpc.find("jsonrpc").and_then(|v| v.as_str()), Some("2.0"));
+    assert_eq!(json_rpc.find("method").and_then(|m| m.as_str()), Some("torrent_get"));
+    assert_eq!(json_rpc.find("id").and_then(|t| t.as_u64()), Some(3));
+    assert_eq!(strings(json_rpc.lookup("params.fields")), vec!["id", "hash_string"]);
+}
+
+#[test]
+fn round_trip() {
+    let legacy = json(r#"{
+        "rpc-version": 18,
+        "seedRatioLimit": 2.5,
+        "activeTorrentCount": 1,
+        "total_size": 1000,
+        "cumulative-stats": {"uploadedBytes": 5, "secondsActive": 60},
+        "units": {"speed-units": ["kB/s", "MB/s"], "size-bytes": 1000},
+        "torrent-added": {"id": 1, "hashString": "abc"},
+        "torrents": [{"id": 1, "file-count": 2, "primary-mime-type": "video/mp4"}]
+    }"#);
+
+    // The daemon uses the snake_case names of the request, so its response to the
+    // encoded arguments must decode back to the legacy names.
+    let req = Protocol::JsonRpc.encode(&RawRequest::new("session-stats", legacy.clone()), 7);
+    let params = req.find("params").cloned().expect("The request has no params.");
+    assert!(params.find("active_torrent_count").is_some());
+    assert!(params.lookup("units.speed_units").is_some());
+
+    let mut response = json(r#"{"jsonrpc": "2.0", "id": 7}"#);
+    if let Value::Object(ref mut obj) = response {
+        obj.insert("result".to_string(), params);
+    }
+
+    let decoded = Protocol::JsonRpc.decode(response, 7).expect("Failed to decode the response.");
+    assert_eq!(decoded, legacy);
+}
+
+#[test]
+fn table_header() {
+    let response = json(r#"{"jsonrpc": "2.0", "id": 1, "result": {
+        "torrents": [["id", "hash_string", "file_count"], [1, "abc", 2]]
+    }}"#);
+
+    let decoded = Protocol::JsonRpc.decode(response, 1).expect("Failed to decode the response.");
+    let rows = decoded.find("torrents").and_then(|t| t.as_array()).expect("No torrents.");
+    assert_eq!(strings(rows.get(0)), vec!["id", "hashString", "file-count"]);
+    assert_eq!(rows[1], json(r#"[1, "abc", 2]"#));
+}
+
+#[test]
+fn legacy_error() {
+    let response = json(r#"{"result": "method name not recognized", "arguments": {}, "tag": 1}"#);
+
+    match Protocol::Legacy.decode(response, 1) {
+        Err(Error::Daemon(daemon::Error::Message(kind, _))) =>
+            assert_eq!(kind, MessageKind::UnrecognizedMethod),
+        res => panic!("Unexpected result: {:?}", res)
+    }
+}
+
+#[test]
+fn json_rpc_error() {
+    let response = json(r#"{"jsonrpc": "2.0", "id": 1,
+                            "error": {"code": -32601, "message": "Method not found"}}"#);
+
+    match Protocol::JsonRpc.decode(response, 1) {
+        Err(Error::Daemon(daemon::Error::JsonRpc(code, _, ref message))) => {
+            assert_eq!(code, JsonRpcCode::MethodNotFound);
+            assert_eq!(message, "Method not found");
+        },
+        res => panic!("Unexpected result: {:?}", res)
+    }
+
+    // The legacy message in `data.error_string` is preferred.
+    let response = json(r#"{"jsonrpc": "2.0", "id": 1, "error": {
+        "code": 4, "message": "Invalid arguments",
+        "data": {"error_string": "invalid or corrupt torrent file"}
+    }}"#);
+
+    match Protocol::JsonRpc.decode(response, 1) {
+        Err(Error::Daemon(daemon::Error::JsonRpc(code, kind, ref message))) => {
+            assert_eq!(code, JsonRpcCode::Other(4));
+            assert_eq!(kind, MessageKind::InvalidTorrent);
+            assert_eq!(message, "invalid or corrupt torrent file");
+        },
+        res => panic!("Unexpected result: {:?}", res)
+    }
+}
@@ -0,0 +1,106 @@
+use query::{Query, SortKey};
+use regex::Regex;
+use torrent::{Torrent, TorrentField};
+use types::Status;
+
+#[test]
+fn fields() {
+    let query = Query::new()
+        .status(Status::Seed)
+        .ratio_above(2.0)
+        .or(Query::new().label("video"))
+        .sort_by(SortKey::Size, false);
+
+    assert_eq!(query.fields(), vec![TorrentField::Status, TorrentField::UploadRatio,
+                                    TorrentField::Labels, TorrentField::TotalSize]);
+}
+
+fn torrents() -> Vec<Torrent> {
+    vec![
+        Torrent {
+            id: Some(1),
+            name: Some("ubuntu-16.10-desktop-amd64.iso".to_string()),
+            status: Some(Status::Seed),
+            upload_ratio: Some(2.31),
+            error: Some(0),
+            labels: Some(vec!["linux".to_string()]),
+            ..Torrent::default()
+        },
+        Torrent {
+            id: Some(2),
+            name: Some("debian-8.6.0-amd64-netinst.iso".to_string()),
+            status: Some(Status::Download),
+            upload_ratio: Some(0.1),
+            error: Some(0),
+            labels: Some(vec![]),
+            ..Torrent::default()
+        },
+        Torrent {
+            id: Some(3),
+            name: Some("big-buck-bunny".to_string()),
+            status: Some(Status::Stopped),
+            upload_ratio: Some(0.0),
+            error: Some(2),
+            labels: Some(vec!["video".to_string()]),
+            ..Torrent::default()
+        },
+        // Lacks every field the conditions need, so no condition matches it.
+        Torrent {
+            id: Some(4),
+            ..Torrent::default()
+        }
+    ]
+}
+
+#[test]
+fn filter_and_sort() {
+    let query = Query::new()
+        .has_error(false)
+        .name(Regex::new(r"\.iso$").unwrap())
+        .sort_by(SortKey::Ratio, true);
+    let ids: Vec<_> = query.apply(torrents()).into_iter().map(|t| t.id).collect();
+    assert_eq!(ids, vec![Some(1), Some(2)]);
+
+    let query = Query::new()
+        .status(Status::Seed)
+        .or(Query::new().label("video"))
+        .not();
+    let ids: Vec<_> = query.limit(1).apply(torrents()).into_iter().map(|t| t.id).collect();
+    assert_eq!(ids, vec![Some(2)]);
+}
+
+#[test]
+fn missing_fields_dont_match() {
+    let seeding = Torrent {
+        id: Some(5),
+        status: Some(Status::Seed),
+        ..Torrent::default()
+    };
+
+    // The negation doesn't match a torrent that lacks the field either.
+    let query = Query::new().label("video").not();
+    assert!(!query.matches(&torrents()[3]));
+    assert!(!query.matches(&seeding));
+
+    // A condition that matches decides `or` even if the other lacks its field...
+    let query = Query::new().status(Status::Seed).or(Query::new().label("video"));
+    assert!(query.matches(&seeding));
+    // ...and one that doesn't match decides its negation.
+    assert!(!query.not().matches(&seeding));
+    assert!(Query::new().status(Status::Stopped).label("video").not().matches(&seeding));
+}
+
+#[test]
+fn missing_fields_sort_last() {
+    let query = Query::new().sort_by(SortKey::Ratio, false);
+    let ids: Vec<_> = query.apply(torrents()).into_iter().map(|t| t.id).collect();
+    assert_eq!(ids, vec![Some(3), Some(2), Some(1), Some(4)]);
+
+    let query = Query::new().sort_by(SortKey::Ratio, true);
+    let ids: Vec<_> = query.apply(torrents()).into_iter().map(|t| t.id).collect();
+    assert_eq!(ids, vec![Some(1), Some(2), Some(3), Some(4)]);
+
+    // The torrents missing the field don't push out the others.
+    let ids: Vec<_> = query.limit(3).apply(torrents()).into_iter().map(|t| t.id).collect();
+    assert_eq!(ids, vec![Some(1), Some(2), Some(3)]);
+}
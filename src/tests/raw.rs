@@ -0,0 +1,36 @@
+use super::{create_fake_transmission, create_transmission};
+use error::Error;
+use error::daemon::MessageKind;
+use requests::{GetTorrent, RawRequest, Request};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use torrent::TorrentField;
+
+#[test]
+fn raw_request() {
+    let tr = create_transmission();
+
+    let res = tr.send(&RawRequest::new("session-get", Value::Object(BTreeMap::new())))
+        .expect("Error while communicating with the server.");
+    assert!(res.find("rpc-version").and_then(|v| v.as_u64()).is_some());
+
+    let err = tr.send(&RawRequest::new("torrent-frobnicate", Value::Object(BTreeMap::new())))
+        .err().expect("The daemon accepted an unknown method.");
+    match *err.root() {
+        Error::Daemon(ref e) => assert_eq!(e.message_kind(), Some(MessageKind::UnrecognizedMethod)),
+        ref err => panic!("Unexpected error: {:?}", err)
+    }
+}
+
+#[test]
+fn extra_arguments() {
+    let tr = create_fake_transmission();
+
+    let req = GetTorrent::new()
+        .field(TorrentField::Id)
+        .with_argument("ids", Value::Array(vec![Value::U64(2)]));
+    let ids: Vec<_> = tr.send(&req).expect("Error while communicating with the server.")
+        .into_iter().map(|t| t.id).collect();
+
+    assert_eq!(ids, vec![Some(2)]);
+}
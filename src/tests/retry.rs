@@ -0,0 +1,103 @@
+use error::{Error, daemon};
+use error::transport::Error as TransportError;
+use hyper::status::StatusCode;
+use retry::{RetryPolicy, RetryOn};
+use std::io;
+use std::time::Duration;
+
+fn timeout() -> Error {
+    Error::Transport(TransportError::Timeout)
+}
+
+fn server_error() -> Error {
+    Error::Daemon(daemon::Error::StatusCode(StatusCode::InternalServerError))
+}
+
+fn connection() -> Error {
+    Error::Io(io::Error::new(io::ErrorKind::ConnectionReset, "reset"))
+}
+
+#[test]
+fn delay_grows_up_to_the_maximum() {
+    let policy = RetryPolicy::new()
+        .backoff(Duration::from_millis(100), Duration::from_secs(1))
+        .jitter(0.0);
+
+    assert_eq!(policy.delay(1), Duration::from_millis(100));
+    assert_eq!(policy.delay(2), Duration::from_millis(200));
+    assert_eq!(policy.delay(3), Duration::from_millis(400));
+    assert_eq!(policy.delay(4), Duration::from_millis(800));
+    assert_eq!(policy.delay(5), Duration::from_secs(1));
+    assert_eq!(policy.delay(64), Duration::from_secs(1));
+}
+
+#[test]
+fn delay_jitter_bounds() {
+    let policy = RetryPolicy::new()
+        .backoff(Duration::from_millis(400), Duration::from_secs(10))
+        .jitter(0.5);
+
+    for _ in 0..100 {
+        let delay = policy.delay(1);
+        assert!(delay >= Duration::from_millis(200), "{:?}", delay);
+        assert!(delay <= Duration::from_millis(400), "{:?}", delay);
+    }
+
+    // The jitter is clamped to 1, so the delay never goes below zero.
+    let policy = policy.jitter(5.0);
+    for _ in 0..100 {
+        assert!(policy.delay(1) <= Duration::from_millis(400));
+    }
+}
+
+#[test]
+fn max_attempts() {
+    let policy = RetryPolicy::new().max_attempts(3);
+    assert!(policy.should_retry(&timeout(), 1, true));
+    assert!(policy.should_retry(&timeout(), 2, true));
+    assert!(!policy.should_retry(&timeout(), 3, true));
+
+    let policy = RetryPolicy::none();
+    assert!(!policy.should_retry(&timeout(), 1, true));
+
+    // At least one attempt is always made.
+    let policy = RetryPolicy::new().max_attempts(0);
+    assert!(!policy.should_retry(&timeout(), 1, true));
+}
+
+#[test]
+fn idempotency() {
+    let policy = RetryPolicy::new();
+    assert!(policy.should_retry(&connection(), 1, true));
+    assert!(!policy.should_retry(&connection(), 1, false));
+
+    let policy = policy.retry_non_idempotent(true);
+    assert!(policy.should_retry(&connection(), 1, false));
+}
+
+#[test]
+fn retry_on() {
+    let policy = RetryPolicy::new();
+    assert!(policy.should_retry(&connection(), 1, true));
+    assert!(policy.should_retry(&server_error(), 1, true));
+    assert!(policy.should_retry(&timeout(), 1, true));
+
+    let policy = policy.retry_on(vec![RetryOn::Timeout]);
+    assert!(!policy.should_retry(&connection(), 1, true));
+    assert!(!policy.should_retry(&server_error(), 1, true));
+    assert!(policy.should_retry(&timeout(), 1, true));
+
+    // Client errors and protocol errors are never retried.
+    let policy = RetryPolicy::new();
+    let conflict = Error::Daemon(daemon::Error::StatusCode(StatusCode::Conflict));
+    assert!(!policy.should_retry(&conflict, 1, true));
+    let mismatch = Error::from(::error::protocol::Error::TagMismatch { expected: 1, received: Some(2) });
+    assert!(!policy.should_retry(&mismatch, 1, true));
+}
+
+#[test]
+fn retry_on_the_root_error() {
+    let policy = RetryPolicy::new();
+    let error = timeout().in_call("torrent-get", 0, None);
+    assert!(policy.should_retry(&error, 1, true));
+}
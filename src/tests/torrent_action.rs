@@ -1,10 +1,11 @@
-use super::create_transmission;
+use super::{create_fake_transmission, create_transmission};
 use requests::{TorrentAction, ActionType, ActionTarget, GetTorrent};
 use torrent::TorrentField;
+use types::Status;
 
 #[test]
 pub fn torrent_action() {
-    let mut tr = create_transmission();
+    let tr = create_transmission();
 
     tr.send(&TorrentAction::new(ActionType::Stop, ActionTarget::All)).unwrap();
 
@@ -22,3 +23,21 @@ pub fn torrent_action() {
 
     println!("Status2: {:?}", torrent2.status.unwrap());
 }
+
+#[test]
+pub fn reannounce_keeps_the_status() {
+    let tr = create_fake_transmission();
+    tr.send(&TorrentAction::new(ActionType::Stop, ActionTarget::Single { id: 1 })).unwrap();
+
+    tr.send(&TorrentAction::new(ActionType::Reannounce, ActionTarget::All)).unwrap();
+
+    let statuses: Vec<_> = tr.send(&GetTorrent::new().fields(vec![TorrentField::Id, TorrentField::Status]))
+        .unwrap().into_iter().map(|t| (t.id, t.status)).collect();
+    assert_eq!(statuses, vec![(Some(1), Some(Status::Stopped)),
+                              (Some(2), Some(Status::Download)),
+                              (Some(3), Some(Status::Stopped))]);
+
+    let active: Vec<_> = tr.send(&GetTorrent::recently_active().field(TorrentField::Id))
+        .unwrap().into_iter().map(|t| t.id).collect();
+    assert_eq!(active, vec![Some(1), Some(2), Some(3)]);
+}
@@ -0,0 +1,35 @@
+use super::create_fake_transmission;
+use requests::{TorrentAction, ActionType, ActionTarget, RawRequest};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use types::Status;
+use watcher::{Watcher, Event};
+
+#[test]
+fn events() {
+    let tr = create_fake_transmission();
+    let mut watcher = Watcher::new();
+
+    let events = watcher.poll(&tr).expect("Error while communicating with the server.");
+    assert!(events.is_empty());
+
+    tr.send(&TorrentAction::new(ActionType::Stop, ActionTarget::Single { id: 1 })).unwrap();
+
+    let mut args = BTreeMap::new();
+    args.insert("ids".to_string(), Value::Array(vec![Value::U64(2)]));
+    tr.send(&RawRequest::new("torrent-remove", Value::Object(args))).unwrap();
+
+    let events = watcher.poll(&tr).expect("Error while communicating with the server.");
+    assert!(events.iter().any(|e| match *e {
+        Event::Removed(2) => true,
+        _ => false
+    }));
+    assert!(events.iter().any(|e| match *e {
+        Event::StatusChanged { id: 1, from: Status::Seed, to: Status::Stopped } => true,
+        _ => false
+    }));
+
+    // The daemon keeps reporting the changes within its window, but they are new only once.
+    let events = watcher.poll(&tr).expect("Error while communicating with the server.");
+    assert!(events.is_empty());
+}
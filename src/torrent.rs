@@ -6,7 +6,7 @@ torrent_proc! {
     /// Struct containing information about a torrent. All fields are optional and wrapped in Option.
     ///
     /// This is generated using procedural macro in the `torrent_macro` crate.
-    #[derive(Deserialize,Debug)]
+    #[derive(Deserialize,Debug,Clone)]
     pub struct Torrent {
         #[time_t] pub activity_date: NaiveDateTime, 
         #[time_t] pub added_date: NaiveDateTime, 
@@ -35,6 +35,7 @@ torrent_proc! {
         pub honors_session_limits: bool,
         pub id: u64,
         pub is_private: bool,
+        pub labels: Vec<String>,
         #[time_t] pub last_announce_time: NaiveDateTime, 
         pub last_scrape_time: u64,
         pub leechers: u64,
@@ -84,10 +85,23 @@ torrent_proc! {
     /// Enum with each variant representing a field in the `Torrent` struct.
     ///
     /// Generated using procedural macro in the `torrent_macro` crate.
-    #[derive(Debug,Clone)]
+    #[derive(Debug,Clone,PartialEq)]
     pub enum TorrentField;
 }
 
+impl TorrentField {
+    /// The oldest version of the RPC interface that supports this field.
+    pub fn min_rpc_version(&self) -> u64 {
+        match self {
+            &TorrentField::BandwidthPriority => 5,
+            &TorrentField::Pieces => 5,
+            &TorrentField::TorrentFile => 5,
+            &TorrentField::Labels => 16,
+            _ => 1
+        }
+    }
+}
+
 /// Contains information about a file on the disk.
 #[derive(Deserialize, Clone, Debug)]
 pub struct File {
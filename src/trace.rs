@@ -0,0 +1,128 @@
+//! Instrumentation of the RPC calls. With the `trace` feature every call is wrapped
+//! in a `tracing` span and the request and response JSON are emitted as debug events.
+//! Without the feature the span and the events compile to nothing.
+
+use error::Result;
+use hyper::Url;
+use serde_json::Value;
+#[cfg(feature = "trace")]
+use std::time::Instant;
+
+pub const REDACTED: &'static str = "[redacted]";
+
+/// Emits a debug level event. The arguments are not evaluated without the `trace` feature.
+#[cfg(feature = "trace")]
+macro_rules! trace_debug {
+    ($($arg:tt)*) => { debug!($($arg)*) }
+}
+
+#[cfg(not(feature = "trace"))]
+macro_rules! trace_debug {
+    ($($arg:tt)*) => {}
+}
+
+/// Replaces the values of the arguments that look like passwords, and of the cookies
+/// that `torrent-add` passes to the tracker.
+pub fn redact_arguments(value: Value) -> Value {
+    match value {
+        Value::Object(obj) => Value::Object(obj.into_iter().map(|(key, value)| {
+            if key.contains("password") || key == "cookies" {
+                (key, Value::String(REDACTED.to_string()))
+            } else {
+                (key, redact_arguments(value))
+            }
+        }).collect()),
+        Value::Array(values) => Value::Array(values.into_iter().map(redact_arguments).collect()),
+        value => value
+    }
+}
+
+/// A url that can be logged without the credentials.
+pub trait RedactUrl {
+    fn redacted(&self) -> String;
+}
+
+impl RedactUrl for Url {
+    fn redacted(&self) -> String {
+        let mut url = self.clone();
+        let _ = url.set_username("");
+        let _ = url.set_password(None);
+        url.to_string()
+    }
+}
+
+#[cfg(feature = "async")]
+impl RedactUrl for ::reqwest::Url {
+    fn redacted(&self) -> String {
+        let mut url = self.clone();
+        let _ = url.set_username("");
+        let _ = url.set_password(None);
+        url.to_string()
+    }
+}
+
+/// The span of a single RPC call.
+#[cfg(feature = "trace")]
+pub struct CallSpan {
+    span: ::tracing::Span,
+    start: Instant
+}
+
+#[cfg(feature = "trace")]
+impl CallSpan {
+    pub fn new<U: RedactUrl>(method: &str, tag: usize, url: &U) -> CallSpan {
+        use tracing::field::Empty;
+
+        CallSpan {
+            span: info_span!("rpc", method = method, tag = tag as u64, url = %url.redacted(),
+                             attempt = Empty, duration_ms = Empty, response_size = Empty,
+                             result = Empty),
+            start: Instant::now()
+        }
+    }
+
+    /// Runs the closure inside the span.
+    pub fn in_scope<F: FnOnce() -> T, T>(&self, f: F) -> T {
+        self.span.in_scope(f)
+    }
+
+    pub fn attempt(&self, attempt: u32) {
+        self.span.record("attempt", &(attempt as u64));
+    }
+
+    pub fn response_size(&self, size: usize) {
+        self.span.record("response_size", &(size as u64));
+    }
+
+    /// Records the duration and the result of the call.
+    pub fn finish<T>(&self, result: &Result<T>) {
+        let elapsed = self.start.elapsed();
+        let millis = elapsed.as_secs() * 1000 + (elapsed.subsec_nanos() / 1_000_000) as u64;
+        self.span.record("duration_ms", &millis);
+
+        match *result {
+            Ok(_) => self.span.record("result", &"ok"),
+            Err(ref e) => self.span.record("result", &&*e.to_string())
+        };
+    }
+}
+
+#[cfg(not(feature = "trace"))]
+pub struct CallSpan;
+
+#[cfg(not(feature = "trace"))]
+impl CallSpan {
+    pub fn new<U: RedactUrl>(_method: &str, _tag: usize, _url: &U) -> CallSpan {
+        CallSpan
+    }
+
+    pub fn in_scope<F: FnOnce() -> T, T>(&self, f: F) -> T {
+        f()
+    }
+
+    pub fn attempt(&self, _attempt: u32) {}
+
+    pub fn response_size(&self, _size: usize) {}
+
+    pub fn finish<T>(&self, _result: &Result<T>) {}
+}
@@ -0,0 +1,203 @@
+use error::*;
+use hyper::Url;
+use hyper::header::Headers;
+use hyper::status::StatusCode;
+use serde_json::{self, Value};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+use super::{Transport, TransportResponse};
+use trace::{REDACTED, redact_arguments};
+use SESSION_HEADER;
+
+/// A recorded request and the daemon's response to it.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Interaction {
+    /// The request headers with the credentials and the session id redacted
+    pub headers: BTreeMap<String, String>,
+    /// The request sent to the daemon
+    pub request: Value,
+    /// The HTTP status code of the response
+    pub status: u16,
+    /// The response received from the daemon, or null if it isn't JSON
+    pub response: Value,
+    /// The body of the response if it isn't JSON, e.g. the HTML page sent along
+    /// with an unsuccessful status code
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>
+}
+
+/// Returns the method and the arguments of the request. The tag is ignored
+/// when matching requests, as it is different on every run.
+fn request_key(request: &Value) -> (Option<&Value>, Option<&Value>) {
+    let args = request.find("arguments").or_else(|| request.find("params"));
+    (request.find("method"), args)
+}
+
+fn redact_headers(headers: &Headers) -> BTreeMap<String, String> {
+    headers.iter().map(|header| {
+        let name = header.name().to_string();
+        let value = if name.eq_ignore_ascii_case("Authorization") ||
+            name.eq_ignore_ascii_case(SESSION_HEADER)
+        {
+            REDACTED.to_string()
+        } else {
+            header.value_string()
+        };
+        (name, value)
+    }).collect()
+}
+
+/// A transport that passes the requests to another transport and writes
+/// the interactions into a cassette file, which can later be served back
+/// by `ReplayTransport`.
+///
+/// The session id handshakes are not recorded. The credentials and the session
+/// id are redacted from the recorded headers, and the cookies and the arguments whose
+/// name contains "password" from the recorded requests.
+pub struct RecordingTransport {
+    inner: RwLock<Box<Transport>>,
+    path: PathBuf,
+    interactions: Mutex<Vec<Interaction>>
+}
+
+impl RecordingTransport {
+    /// Records the interactions made using `inner` into the cassette at `path`.
+    /// The file is rewritten after every interaction.
+    pub fn new<P: Into<PathBuf>>(inner: Box<Transport>, path: P) -> RecordingTransport {
+        RecordingTransport {
+            inner: RwLock::new(inner),
+            path: path.into(),
+            interactions: Mutex::new(Vec::new())
+        }
+    }
+
+    /// Replaces the transport the requests are passed to. The interactions recorded
+    /// so far are kept.
+    pub fn set_inner(&self, inner: Box<Transport>) {
+        *self.inner.write().unwrap() = inner;
+    }
+}
+
+impl RecordingTransport {
+    /// Adds the interaction to the cassette unless it is a session id handshake.
+    fn record(&self, headers: BTreeMap<String, String>, body: &[u8],
+              response: &TransportResponse) -> Result<()> {
+        if response.status != StatusCode::Conflict {
+            let request: Value = serde_json::from_slice(body)?;
+            let (recorded_response, recorded_body) = match serde_json::from_slice(&response.body) {
+                Ok(value) => (value, None),
+                Err(_) => (Value::Null, Some(String::from_utf8_lossy(&response.body).into_owned()))
+            };
+
+            let mut interactions = self.interactions.lock().unwrap();
+            interactions.push(Interaction {
+                headers: headers,
+                request: redact_arguments(request),
+                status: response.status.to_u16(),
+                response: recorded_response,
+                body: recorded_body
+            });
+
+            let mut file = try!(File::create(&self.path));
+            serde_json::to_writer_pretty(&mut file, &*interactions)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Transport for RecordingTransport {
+    fn post(&self, url: &Url, headers: Headers, body: &[u8]) -> Result<TransportResponse> {
+        let recorded_headers = redact_headers(&headers);
+        let response = self.inner.read().unwrap().post(url, headers, body)?;
+        self.record(recorded_headers, body, &response)?;
+        Ok(response)
+    }
+
+    fn post_timeout(&self, url: &Url, headers: Headers, body: &[u8],
+                    timeout: Duration) -> Result<TransportResponse> {
+        let recorded_headers = redact_headers(&headers);
+        let response = self.inner.read().unwrap().post_timeout(url, headers, body, timeout)?;
+        self.record(recorded_headers, body, &response)?;
+        Ok(response)
+    }
+}
+
+/// A transport that serves the responses recorded by `RecordingTransport`.
+///
+/// The requests are matched to the recorded ones by the method and the arguments,
+/// which are redacted like the recorded ones. If the same request was recorded
+/// several times, the responses are served in the recorded order. Responses that
+/// aren't JSON are served as they were recorded.
+pub struct ReplayTransport {
+    interactions: Vec<Interaction>,
+    used: Mutex<Vec<bool>>
+}
+
+impl ReplayTransport {
+    /// Creates a transport that serves the given interactions.
+    pub fn new(interactions: Vec<Interaction>) -> ReplayTransport {
+        let used = vec![false; interactions.len()];
+        ReplayTransport {
+            interactions: interactions,
+            used: Mutex::new(used)
+        }
+    }
+
+    /// Reads the interactions from a cassette file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<ReplayTransport> {
+        let file = try!(File::open(path));
+        let interactions = serde_json::from_reader(file)?;
+        Ok(ReplayTransport::new(interactions))
+    }
+}
+
+impl Transport for ReplayTransport {
+    fn post(&self, _url: &Url, _headers: Headers, body: &[u8]) -> Result<TransportResponse> {
+        // The recorded requests are redacted, so the live one has to be too.
+        let request = redact_arguments(serde_json::from_slice(body)?);
+        let key = request_key(&request);
+
+        let mut used = self.used.lock().unwrap();
+        let index = self.interactions.iter().enumerate()
+            .position(|(i, interaction)| !used[i] && request_key(&interaction.request) == key);
+
+        let interaction = match index {
+            Some(i) => {
+                used[i] = true;
+                &self.interactions[i]
+            },
+            None => {
+                let method = key.0.and_then(|m| m.as_str()).unwrap_or("").to_string();
+                return Err(transport::Error::NotRecorded(method).into());
+            }
+        };
+
+        if let Some(ref body) = interaction.body {
+            return Ok(TransportResponse {
+                status: StatusCode::from_u16(interaction.status),
+                headers: Headers::new(),
+                body: body.clone().into_bytes()
+            });
+        }
+
+        // Echo the tag of the request instead of the recorded one.
+        let mut response = interaction.response.clone();
+        if let Value::Object(ref mut obj) = response {
+            for key in &["tag", "id"] {
+                if let Some(tag) = request.find(key) {
+                    obj.insert(key.to_string(), tag.clone());
+                }
+            }
+        }
+
+        Ok(TransportResponse {
+            status: StatusCode::from_u16(interaction.status),
+            headers: Headers::new(),
+            body: serde_json::to_vec(&response)?
+        })
+    }
+}
@@ -0,0 +1,99 @@
+use hyper;
+use hyper::net::{HttpStream, NetworkConnector};
+use std::cell::Cell;
+use std::cmp;
+use std::io;
+use std::time::{Duration, Instant};
+use super::proxy::{Proxy, tcp_connect};
+
+thread_local! {
+    /// The time the connections opened by this thread must be ready by. hyper 0.9
+    /// connects on the thread that sends the request, so this is how a deadline
+    /// reaches the pooled connector.
+    static TIME_LIMIT: Cell<Option<Instant>> = Cell::new(None)
+}
+
+/// Restores the previous time limit, also if the request panics.
+struct ResetTimeLimit(Option<Instant>);
+
+impl Drop for ResetTimeLimit {
+    fn drop(&mut self) {
+        TIME_LIMIT.with(|limit| limit.set(self.0));
+    }
+}
+
+/// Runs `f` with the timeouts of the connections it opens limited to the time left until `until`.
+pub fn with_time_limit<T, F: FnOnce() -> T>(until: Instant, f: F) -> T {
+    let _reset = ResetTimeLimit(TIME_LIMIT.with(|limit| limit.replace(Some(until))));
+    f()
+}
+
+/// Limits the timeout to the time left, if there's a time limit. A zero timeout
+/// isn't allowed by the sockets, so at least a millisecond is left.
+fn limited(timeout: Option<Duration>) -> Option<Duration> {
+    match TIME_LIMIT.with(|limit| limit.get()) {
+        Some(until) => {
+            let now = Instant::now();
+            let left = if until > now {
+                cmp::max(until - now, Duration::from_millis(1))
+            } else {
+                Duration::from_millis(1)
+            };
+            Some(timeout.map_or(left, |timeout| cmp::min(timeout, left)))
+        },
+        None => timeout
+    }
+}
+
+/// Opens the TCP connections used by `HttpTransport`, either directly
+/// or through a proxy.
+pub struct Connector {
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+    proxy: Option<Proxy>,
+    proxy_from_env: bool
+}
+
+impl Connector {
+    pub fn new(connect_timeout: Option<Duration>, read_timeout: Option<Duration>,
+               write_timeout: Option<Duration>, proxy: Option<Proxy>, proxy_from_env: bool) -> Connector {
+        Connector {
+            connect_timeout: connect_timeout,
+            read_timeout: read_timeout,
+            write_timeout: write_timeout,
+            proxy: proxy,
+            proxy_from_env: proxy_from_env
+        }
+    }
+}
+
+impl NetworkConnector for Connector {
+    type Stream = HttpStream;
+
+    fn connect(&self, host: &str, port: u16, scheme: &str) -> hyper::Result<HttpStream> {
+        let proxy = match self.proxy {
+            Some(ref proxy) => Some(proxy.clone()),
+            // `HttpTransport::post` reports an invalid proxy url before connecting.
+            None if self.proxy_from_env => try!(Proxy::from_env(scheme, host).map_err(|e| {
+                io::Error::new(io::ErrorKind::InvalidInput, e.to_string())
+            })),
+            None => None
+        };
+
+        let connect_timeout = limited(self.connect_timeout);
+        let stream = match proxy {
+            Some(proxy) => try!(proxy.connect(host, port, connect_timeout,
+                                              limited(self.read_timeout), limited(self.write_timeout))),
+            None => {
+                // Bound the TLS handshake, which happens before hyper sets the timeouts.
+                let stream = try!(tcp_connect(host, port, connect_timeout));
+                try!(stream.set_read_timeout(limited(self.read_timeout)));
+                try!(stream.set_write_timeout(limited(self.write_timeout)));
+                stream
+            }
+        };
+
+        Ok(HttpStream(stream))
+    }
+}
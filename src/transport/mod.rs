@@ -0,0 +1,225 @@
+//! The transports used to deliver the requests to the daemon.
+
+mod cassette;
+mod connector;
+mod proxy;
+mod tls;
+#[cfg(unix)]
+mod unix;
+
+pub use self::cassette::{Interaction, RecordingTransport, ReplayTransport};
+pub use self::proxy::{Proxy, ProxyKind};
+pub use self::tls::{TlsConfig, TlsClient};
+
+use error::*;
+use hyper::{self, Client, Url};
+use hyper::client::pool::Pool;
+use hyper::header::Headers;
+use hyper::net::{HttpsConnector, NetworkConnector};
+use hyper::status::StatusCode;
+use self::connector::Connector;
+use std::cmp;
+use std::io::{self, Read};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A response received from the daemon.
+pub struct TransportResponse {
+    /// The HTTP status code of the response
+    pub status: StatusCode,
+    /// The HTTP headers of the response
+    pub headers: Headers,
+    /// The body of the response
+    pub body: Vec<u8>
+}
+
+/// A trait for delivering the serialized requests to the daemon.
+///
+/// `Transmission` takes care of serializing the requests, the session id handshake
+/// and authentication, so a transport only needs to deliver the bytes and return
+/// whatever the daemon responded with.
+///
+/// Transports must be thread safe as `Transmission` can be shared between threads.
+pub trait Transport: Send + Sync {
+    /// Posts the JSON encoded request to the given url with the given headers.
+    fn post(&self, url: &Url, headers: Headers, body: &[u8]) -> Result<TransportResponse>;
+
+    /// Like `post`, but fails with `transport::Error::Timeout` if the request takes
+    /// longer than `timeout`. Used to keep the requests within the deadline set using
+    /// `Transmission::set_deadline`. The default implementation ignores the timeout.
+    fn post_timeout(&self, url: &Url, headers: Headers, body: &[u8],
+                    _timeout: Duration) -> Result<TransportResponse> {
+        self.post(url, headers, body)
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for Arc<T> {
+    fn post(&self, url: &Url, headers: Headers, body: &[u8]) -> Result<TransportResponse> {
+        (**self).post(url, headers, body)
+    }
+
+    fn post_timeout(&self, url: &Url, headers: Headers, body: &[u8],
+                    timeout: Duration) -> Result<TransportResponse> {
+        (**self).post_timeout(url, headers, body, timeout)
+    }
+}
+
+/// The configuration of `HttpTransport`.
+#[derive(Clone, Debug, Default)]
+pub struct HttpConfig {
+    /// The maximum time to wait for a connection to be established
+    pub connect_timeout: Option<Duration>,
+    /// The maximum time to wait for data from the daemon
+    pub read_timeout: Option<Duration>,
+    /// The maximum time to wait for the daemon to accept data
+    pub write_timeout: Option<Duration>,
+    /// The TLS client used for HTTPS connections. Uses the system's root
+    /// certificates if not set.
+    pub tls: Option<TlsClient>,
+    /// The proxy through which the daemon is connected
+    pub proxy: Option<Proxy>,
+    /// If true and no proxy is set, the proxy is read from the environment variables.
+    /// See `Proxy::from_env`.
+    pub proxy_from_env: bool,
+    /// The path the requests are sent to when connecting over a Unix domain socket.
+    /// Defaults to `/transmission/rpc`.
+    pub unix_rpc_path: Option<String>
+}
+
+/// The default transport which uses HTTP to talk to the daemon.
+///
+/// Besides `http` and `https` urls, `unix` urls such as `unix:///run/transmission/rpc.sock`
+/// can be used to talk to a daemon listening on a Unix domain socket. The requests are
+/// then sent to `HttpConfig::unix_rpc_path`, which defaults to `/transmission/rpc`.
+pub struct HttpTransport {
+    pool: Arc<Pool<HttpsConnector<TlsClient, Connector>>>,
+    config: HttpConfig
+}
+
+/// Lets the clients with different timeouts share the connections and the TLS context.
+struct SharedPool(Arc<Pool<HttpsConnector<TlsClient, Connector>>>);
+
+impl NetworkConnector for SharedPool {
+    type Stream = <Pool<HttpsConnector<TlsClient, Connector>> as NetworkConnector>::Stream;
+
+    fn connect(&self, host: &str, port: u16, scheme: &str) -> hyper::Result<Self::Stream> {
+        self.0.connect(host, port, scheme)
+    }
+}
+
+impl HttpTransport {
+    /// Creates a new HTTP transport without timeouts.
+    pub fn new() -> HttpTransport {
+        HttpTransport::with_config(&HttpConfig::default())
+    }
+
+    /// Creates a new HTTP transport using the given configuration.
+    pub fn with_config(config: &HttpConfig) -> HttpTransport {
+        let tls = config.tls.clone().unwrap_or_default();
+        let connector = HttpsConnector::with_connector(tls, Connector::new(config.connect_timeout,
+                                                                           config.read_timeout,
+                                                                           config.write_timeout,
+                                                                           config.proxy.clone(),
+                                                                           config.proxy_from_env));
+
+        HttpTransport {
+            pool: Arc::new(Pool::with_connector(Default::default(), connector)),
+            config: config.clone()
+        }
+    }
+
+    /// Creates a client with the given timeouts that uses the pooled connections.
+    fn client(&self, read_timeout: Option<Duration>, write_timeout: Option<Duration>) -> Client {
+        let mut client = Client::with_connector(SharedPool(self.pool.clone()));
+        client.set_read_timeout(read_timeout);
+        client.set_write_timeout(write_timeout);
+        client
+    }
+
+    /// Creates a client that connects to the Unix domain socket at the url's path.
+    #[cfg(unix)]
+    fn unix_client(&self, url: &Url, read_timeout: Option<Duration>,
+                   write_timeout: Option<Duration>) -> Result<Client> {
+        let mut client = Client::with_connector(self::unix::UnixConnector::new(url.path()));
+        client.set_read_timeout(read_timeout);
+        client.set_write_timeout(write_timeout);
+        Ok(client)
+    }
+
+    #[cfg(not(unix))]
+    fn unix_client(&self, _url: &Url, _read_timeout: Option<Duration>,
+                   _write_timeout: Option<Duration>) -> Result<Client> {
+        Err(Error::Io(io::Error::new(io::ErrorKind::InvalidInput,
+                                     "Unix domain sockets are not supported")))
+    }
+
+    fn post_with(&self, url: &Url, headers: Headers, body: &[u8], read_timeout: Option<Duration>,
+                 write_timeout: Option<Duration>) -> Result<TransportResponse> {
+        let (client, url) = if url.scheme() == "unix" {
+            let client = self.unix_client(url, read_timeout, write_timeout)?;
+            let mut rpc_url = Url::parse("http://localhost/transmission/rpc").unwrap();
+            if let Some(ref path) = self.config.unix_rpc_path {
+                rpc_url.set_path(path);
+            }
+            (client, rpc_url)
+        } else {
+            // Report an invalid proxy in the environment instead of bypassing it.
+            if self.config.proxy.is_none() && self.config.proxy_from_env {
+                Proxy::from_env(url.scheme(), url.host_str().unwrap_or(""))?;
+            }
+            (self.client(read_timeout, write_timeout), url.clone())
+        };
+
+        let mut response = client.post(url)
+            .headers(headers)
+            .body(body)
+            .send()
+            .map_err(map_timeout)?;
+
+        let mut body = Vec::new();
+        response.read_to_end(&mut body).map_err(map_timeout)?;
+
+        Ok(TransportResponse {
+            status: response.status,
+            headers: response.headers.clone(),
+            body: body
+        })
+    }
+}
+
+fn is_timeout(e: &io::Error) -> bool {
+    e.kind() == io::ErrorKind::TimedOut || e.kind() == io::ErrorKind::WouldBlock
+}
+
+/// Converts the errors caused by timeouts into `transport::Error::Timeout`.
+fn map_timeout<E: Into<Error>>(e: E) -> Error {
+    let e = e.into();
+    let timed_out = match e {
+        Error::Transport(transport::Error::Http(hyper::Error::Io(ref e))) | Error::Io(ref e) => is_timeout(e),
+        _ => false
+    };
+
+    if timed_out {
+        transport::Error::Timeout.into()
+    } else {
+        e
+    }
+}
+
+impl Transport for HttpTransport {
+    fn post(&self, url: &Url, headers: Headers, body: &[u8]) -> Result<TransportResponse> {
+        self.post_with(url, headers, body, self.config.read_timeout, self.config.write_timeout)
+    }
+
+    /// Limits the timeouts to `timeout`. The pooled connections and the TLS context
+    /// are reused; a new connection, including the proxy handshake, is also bounded.
+    fn post_timeout(&self, url: &Url, headers: Headers, body: &[u8],
+                    timeout: Duration) -> Result<TransportResponse> {
+        let limit = |t: Option<Duration>| Some(t.map_or(timeout, |t| cmp::min(t, timeout)));
+
+        connector::with_time_limit(Instant::now() + timeout, || {
+            self.post_with(url, headers, body, limit(self.config.read_timeout),
+                           limit(self.config.write_timeout))
+        })
+    }
+}
@@ -0,0 +1,162 @@
+use error::*;
+use hyper;
+use hyper::net::{HttpStream, SslClient};
+use openssl::crypto::hash::Type as HashType;
+use openssl::ssl::{Ssl, SslContext, SslMethod, SslStream, SSL_VERIFY_PEER, SSL_VERIFY_NONE};
+use openssl::x509::{X509FileType, X509StoreContext};
+use openssl_verify::verify_callback;
+use rustc_serialize::hex::FromHex;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// TLS options used when connecting to the daemon over HTTPS.
+///
+/// By default the daemon's certificate is verified against the system's root
+/// certificates, which is also what happens if no TLS options are given at all.
+#[derive(Clone, Debug, Default)]
+pub struct TlsConfig {
+    root_certificates: Vec<PathBuf>,
+    client_certificate: Option<(PathBuf, PathBuf)>,
+    pins: Vec<String>,
+    insecure: bool
+}
+
+impl TlsConfig {
+    /// Creates the default TLS options.
+    pub fn new() -> TlsConfig {
+        TlsConfig::default()
+    }
+
+    /// Trusts the certificates in the given PEM file in addition to the system's
+    /// root certificates. Useful when the daemon uses a certificate signed by an
+    /// internal CA.
+    pub fn add_root_certificate<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.root_certificates.push(path.into());
+        self
+    }
+
+    /// Authenticates to the server using the certificate and the private key
+    /// in the given PEM files.
+    pub fn client_certificate<P: Into<PathBuf>>(mut self, certificate: P, key: P) -> Self {
+        self.client_certificate = Some((certificate.into(), key.into()));
+        self
+    }
+
+    /// Accepts only a server certificate with the given SHA-256 fingerprint, given as
+    /// a hex string. Colons are allowed, e.g. `AB:CD:...`. Can be called several times
+    /// to accept any of the pinned certificates.
+    pub fn pin_sha256<S: Into<String>>(mut self, fingerprint: S) -> Self {
+        self.pins.push(fingerprint.into());
+        self
+    }
+
+    /// Disables the verification of the server's certificate, including the pins.
+    /// The connection is still encrypted but anyone can impersonate the daemon,
+    /// so this should only be used for testing.
+    pub fn insecure(mut self, insecure: bool) -> Self {
+        self.insecure = insecure;
+        self
+    }
+
+    /// Loads the certificates and creates the TLS client.
+    pub fn build(&self) -> Result<TlsClient> {
+        let mut pins = Vec::with_capacity(self.pins.len());
+        for pin in &self.pins {
+            let hex: String = pin.chars().filter(|c| *c != ':').collect();
+            match hex.from_hex() {
+                Ok(ref bytes) if bytes.len() == 32 => pins.push(bytes.clone()),
+                _ => return Err(transport::Error::InvalidPin(pin.clone()).into())
+            }
+        }
+
+        let mut context = try!(SslContext::new(SslMethod::Sslv23));
+        try!(context.set_default_verify_paths());
+
+        for path in &self.root_certificates {
+            try!(context.set_CA_file(path));
+        }
+
+        if let Some((ref certificate, ref key)) = self.client_certificate {
+            try!(context.set_certificate_file(certificate, X509FileType::PEM));
+            try!(context.set_private_key_file(key, X509FileType::PEM));
+            try!(context.check_private_key());
+        }
+
+        Ok(TlsClient {
+            context: Ok(Arc::new(context)),
+            pins: Arc::new(pins),
+            insecure: self.insecure
+        })
+    }
+}
+
+/// Wraps the connections to the daemon in TLS according to a `TlsConfig`.
+#[derive(Clone)]
+pub struct TlsClient {
+    /// The context, or why the default context could not be created
+    context: ::std::result::Result<Arc<SslContext>, String>,
+    pins: Arc<Vec<Vec<u8>>>,
+    insecure: bool
+}
+
+impl fmt::Debug for TlsClient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TlsClient")
+            .field("pins", &self.pins.len())
+            .field("insecure", &self.insecure)
+            .finish()
+    }
+}
+
+/// Uses the default `TlsConfig`. If the TLS context can't be created, the error is
+/// returned when connecting over HTTPS, so plain HTTP connections still work.
+impl Default for TlsClient {
+    fn default() -> TlsClient {
+        TlsConfig::new().build().unwrap_or_else(|e| TlsClient {
+            context: Err(format!("failed to create the default TLS context: {}", e)),
+            pins: Arc::new(Vec::new()),
+            insecure: false
+        })
+    }
+}
+
+impl SslClient for TlsClient {
+    type Stream = SslStream<HttpStream>;
+
+    fn wrap_client(&self, stream: HttpStream, host: &str) -> hyper::Result<Self::Stream> {
+        let context = match self.context {
+            Ok(ref context) => context,
+            Err(ref e) => return Err(hyper::Error::Ssl(Box::new(io::Error::new(io::ErrorKind::Other,
+                                                                                 e.clone()))))
+        };
+        let mut ssl = try!(Ssl::new(context));
+        try!(ssl.set_hostname(host));
+
+        if self.insecure {
+            ssl.set_verify(SSL_VERIFY_NONE, None);
+        } else {
+            let host = host.to_owned();
+            let pins = self.pins.clone();
+
+            ssl.set_verify_callback(SSL_VERIFY_PEER, move |preverified: bool, ctx: &X509StoreContext| {
+                if !verify_callback(&host, preverified, ctx) {
+                    return false;
+                }
+
+                // Only the daemon's own certificate is pinned, not the CAs.
+                if pins.is_empty() || ctx.error_depth() != 0 {
+                    return true;
+                }
+
+                ctx.get_current_cert()
+                    .and_then(|cert| cert.fingerprint(HashType::SHA256))
+                    .map(|fingerprint| pins.iter().any(|pin| *pin == fingerprint))
+                    .unwrap_or(false)
+            });
+        }
+
+        SslStream::connect(ssl, stream).map_err(From::from)
+    }
+}
@@ -0,0 +1,66 @@
+use hyper;
+use hyper::net::{NetworkConnector, NetworkStream};
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Connects to a daemon listening on a Unix domain socket. The host and the port
+/// of the url are ignored.
+pub struct UnixConnector {
+    path: PathBuf
+}
+
+impl UnixConnector {
+    pub fn new<P: Into<PathBuf>>(path: P) -> UnixConnector {
+        UnixConnector {
+            path: path.into()
+        }
+    }
+}
+
+impl NetworkConnector for UnixConnector {
+    type Stream = UnixHttpStream;
+
+    fn connect(&self, _host: &str, _port: u16, _scheme: &str) -> hyper::Result<UnixHttpStream> {
+        Ok(UnixHttpStream(try!(UnixStream::connect(&self.path))))
+    }
+}
+
+/// A connection to the daemon over a Unix domain socket.
+pub struct UnixHttpStream(UnixStream);
+
+impl Read for UnixHttpStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Write for UnixHttpStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl NetworkStream for UnixHttpStream {
+    fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        Err(io::Error::new(io::ErrorKind::Other, "Unix domain sockets have no IP address"))
+    }
+
+    fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_read_timeout(dur)
+    }
+
+    fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_write_timeout(dur)
+    }
+
+    fn close(&mut self, how: Shutdown) -> io::Result<()> {
+        self.0.shutdown(how)
+    }
+}
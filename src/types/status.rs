@@ -1,5 +1,5 @@
 /// Enum that represents the torrent's current state
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Status {
     /// Torrent is stopped
     Stopped,
@@ -0,0 +1,351 @@
+//! Watching the daemon for changes in the torrents.
+
+use error::Result;
+use requests::{GetTorrent, RECENTLY_ACTIVE_SECONDS};
+use responses::GetTorrent as GetTorrentResponse;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::thread;
+use std::time::{Duration, Instant};
+use torrent::{Torrent, TorrentField};
+use types::Status;
+use Transmission;
+#[cfg(feature = "async")]
+use async_client::ResponseFuture;
+#[cfg(feature = "async")]
+use error::Error;
+#[cfg(feature = "async")]
+use futures::{Async, Future, Poll, Stream};
+#[cfg(feature = "async")]
+use std::io;
+#[cfg(feature = "async")]
+use tokio::timer::Delay;
+#[cfg(feature = "async")]
+use AsyncTransmission;
+
+/// The fields that are always requested, as they are needed to detect the changes.
+const REQUIRED_FIELDS: [TorrentField; 5] = [TorrentField::Id, TorrentField::Status,
+                                            TorrentField::PercentDone, TorrentField::Error,
+                                            TorrentField::ErrorString];
+
+/// Returns true if the changes made after `since` are still reported by `recently-active`
+/// requests. A few seconds are left for the latency of the requests.
+pub fn is_recently_active(since: Instant) -> bool {
+    since.elapsed() + Duration::from_secs(5) < Duration::from_secs(RECENTLY_ACTIVE_SECONDS)
+}
+
+/// A change in the torrents noticed by `Watcher`.
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// A torrent was added. Contains the fields requested by the watcher.
+    Added(Torrent),
+    /// The torrent with the id was removed.
+    Removed(u64),
+    /// The status of a torrent changed.
+    StatusChanged { id: u64, from: Status, to: Status },
+    /// A torrent finished downloading.
+    Completed(u64),
+    /// The daemon reported an error for a torrent. Contains the error message.
+    ErrorRaised { id: u64, error: String },
+    /// The error of a torrent was cleared.
+    ErrorCleared(u64),
+    /// The labels of a torrent changed. Only reported if `TorrentField::Labels` is watched.
+    LabelsChanged { id: u64, from: Vec<String>, to: Vec<String> }
+}
+
+/// The state of a torrent the events are derived from.
+struct Known {
+    status: Option<Status>,
+    complete: bool,
+    error: bool,
+    labels: Option<Vec<String>>
+}
+
+impl<'a> From<&'a Torrent> for Known {
+    fn from(torrent: &Torrent) -> Known {
+        Known {
+            status: torrent.status,
+            complete: torrent.percent_done.map(|p| p >= 1.0).unwrap_or(false),
+            error: torrent.error.map(|e| e != 0).unwrap_or(false),
+            labels: torrent.labels.clone()
+        }
+    }
+}
+
+/// Polls the daemon for the recently active torrents and reports the changes as events.
+///
+/// The first poll fetches every torrent to learn the current state. Events are
+/// reported only for the changes after that, unless `include_existing` is set.
+/// The daemon reports the torrents that have been active within the last minute, so if
+/// more time passes between two successful polls, every torrent is fetched again.
+///
+/// ```no_run
+/// # use transmission_rpc::{Transmission, Watcher, Event};
+/// # use std::time::Duration;
+/// let tr = Transmission::new();
+/// for event in Watcher::new().interval(Duration::from_secs(5)).iter(&tr) {
+///     if let Ok(Event::Completed(id)) = event {
+///         println!("Torrent {} completed", id);
+///     }
+/// }
+/// ```
+pub struct Watcher {
+    interval: Duration,
+    fields: Vec<TorrentField>,
+    include_existing: bool,
+    known: Option<BTreeMap<u64, Known>>,
+    /// When the last successful poll was sent
+    polled_at: Option<Instant>
+}
+
+impl Watcher {
+    /// Creates a watcher that polls every 2 seconds and watches the name and the labels
+    /// in addition to the fields needed to detect the changes.
+    pub fn new() -> Watcher {
+        Watcher {
+            interval: Duration::from_secs(2),
+            fields: vec![TorrentField::Name, TorrentField::Labels],
+            include_existing: false,
+            known: None,
+            polled_at: None
+        }
+    }
+
+    /// Sets the time between the polls. With an interval close to a minute or longer
+    /// every poll fetches all the torrents.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets the fields requested in addition to the ones needed to detect the changes.
+    /// The labels require RPC version 16; leave `TorrentField::Labels` out for older daemons.
+    pub fn fields(mut self, fields: Vec<TorrentField>) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    /// Reports the torrents found by the first poll as added.
+    pub fn include_existing(mut self, include_existing: bool) -> Self {
+        self.include_existing = include_existing;
+        self
+    }
+
+    /// Returns an iterator that polls the daemon and yields the events. The iterator
+    /// never ends; an error is yielded if a poll fails and the next poll is tried again.
+    pub fn iter(self, tr: &Transmission) -> Events {
+        Events {
+            watcher: self,
+            tr: tr,
+            pending: VecDeque::new(),
+            last_poll: None
+        }
+    }
+
+    /// Polls the daemon once and returns the events.
+    pub fn poll(&mut self, tr: &Transmission) -> Result<Vec<Event>> {
+        let full = self.needs_full();
+        let started = Instant::now();
+        let response = tr.send(&self.request(full))?;
+        Ok(self.apply(response, full, started))
+    }
+
+    /// Returns a stream that polls the daemon and yields the events. Like `iter`, the
+    /// stream never ends; an error is returned if a poll fails, and polling the stream
+    /// again tries the next poll after the interval. The stream must be run on a
+    /// `tokio` runtime.
+    #[cfg(feature = "async")]
+    pub fn stream(self, tr: &AsyncTransmission) -> EventStream {
+        EventStream {
+            watcher: self,
+            tr: tr,
+            pending: VecDeque::new(),
+            state: StreamState::Waiting(Delay::new(Instant::now()))
+        }
+    }
+
+    /// Returns true if the next poll has to fetch every torrent.
+    fn needs_full(&self) -> bool {
+        match (self.known.as_ref(), self.polled_at) {
+            (Some(_), Some(polled_at)) => !is_recently_active(polled_at),
+            _ => true
+        }
+    }
+
+    fn request(&self, full: bool) -> GetTorrent {
+        let mut fields = REQUIRED_FIELDS.to_vec();
+        for field in &self.fields {
+            if !fields.contains(field) {
+                fields.push(field.clone());
+            }
+        }
+
+        if full {
+            GetTorrent::new().fields(fields)
+        } else {
+            GetTorrent::recently_active().fields(fields)
+        }
+    }
+
+    /// Updates the known state with the response to a poll sent at `started`
+    /// and returns the changes.
+    fn apply(&mut self, response: GetTorrentResponse, full: bool, started: Instant) -> Vec<Event> {
+        let mut events = Vec::new();
+        let first = self.known.is_none();
+        let report_added = !first || self.include_existing;
+        let known = self.known.get_or_insert_with(BTreeMap::new);
+        self.polled_at = Some(started);
+
+        let mut removed = response.removed().to_vec();
+        let torrents: Vec<Torrent> = response.into_iter().collect();
+        if full {
+            // A full fetch lists every torrent; the known ones missing from it were removed.
+            let ids: BTreeSet<u64> = torrents.iter().filter_map(|t| t.id).collect();
+            removed = known.keys().filter(|id| !ids.contains(id)).cloned().collect();
+        }
+
+        for id in removed {
+            if known.remove(&id).is_some() {
+                events.push(Event::Removed(id));
+            }
+        }
+
+        for torrent in torrents {
+            let id = match torrent.id {
+                Some(id) => id,
+                None => continue
+            };
+            let new = Known::from(&torrent);
+
+            match known.insert(id, new) {
+                Some(old) => changes(id, &old, &known[&id], &torrent, &mut events),
+                None => if report_added {
+                    events.push(Event::Added(torrent))
+                }
+            }
+        }
+
+        events
+    }
+}
+
+/// Compares the old and the new state of a torrent.
+fn changes(id: u64, old: &Known, new: &Known, torrent: &Torrent, events: &mut Vec<Event>) {
+    if let (Some(from), Some(to)) = (old.status, new.status) {
+        if from != to {
+            events.push(Event::StatusChanged { id: id, from: from, to: to });
+        }
+    }
+
+    if !old.complete && new.complete {
+        events.push(Event::Completed(id));
+    }
+
+    if !old.error && new.error {
+        let error = torrent.error_string.clone().unwrap_or_default();
+        events.push(Event::ErrorRaised { id: id, error: error });
+    } else if old.error && !new.error {
+        events.push(Event::ErrorCleared(id));
+    }
+
+    if let (Some(from), Some(to)) = (old.labels.as_ref(), new.labels.as_ref()) {
+        if from != to {
+            events.push(Event::LabelsChanged { id: id, from: from.clone(), to: to.clone() });
+        }
+    }
+}
+
+/// An iterator over the events noticed by a `Watcher`. Created using `Watcher::iter`.
+pub struct Events<'a> {
+    watcher: Watcher,
+    tr: &'a Transmission,
+    pending: VecDeque<Event>,
+    last_poll: Option<Instant>
+}
+
+impl<'a> Iterator for Events<'a> {
+    type Item = Result<Event>;
+
+    fn next(&mut self) -> Option<Result<Event>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(Ok(event));
+            }
+
+            if let Some(last_poll) = self.last_poll {
+                let elapsed = last_poll.elapsed();
+                if elapsed < self.watcher.interval {
+                    thread::sleep(self.watcher.interval - elapsed);
+                }
+            }
+            self.last_poll = Some(Instant::now());
+
+            match self.watcher.poll(self.tr) {
+                Ok(events) => self.pending.extend(events),
+                Err(e) => return Some(Err(e))
+            }
+        }
+    }
+}
+
+/// A stream of the events noticed by a `Watcher`. Created using `Watcher::stream`.
+#[cfg(feature = "async")]
+pub struct EventStream<'a> {
+    watcher: Watcher,
+    tr: &'a AsyncTransmission,
+    pending: VecDeque<Event>,
+    state: StreamState
+}
+
+#[cfg(feature = "async")]
+enum StreamState {
+    /// Waiting for the next poll.
+    Waiting(Delay),
+    /// Waiting for the response to a poll.
+    Polling {
+        response: ResponseFuture<GetTorrentResponse>,
+        full: bool,
+        started: Instant
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'a> Stream for EventStream<'a> {
+    type Item = Event;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<Event>, Error> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(Async::Ready(Some(event)));
+            }
+
+            let polled = match self.state {
+                StreamState::Waiting(ref mut delay) => {
+                    try_ready!(delay.poll().map_err(|e| io::Error::new(io::ErrorKind::Other, e)));
+                    None
+                },
+                StreamState::Polling { ref mut response, full, started } => match response.poll() {
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    Ok(Async::Ready(response)) => Some((Ok(response), full, started)),
+                    Err(e) => Some((Err(e), full, started))
+                }
+            };
+
+            match polled {
+                None => {
+                    let full = self.watcher.needs_full();
+                    self.state = StreamState::Polling {
+                        response: self.tr.send(&self.watcher.request(full)),
+                        full: full,
+                        started: Instant::now()
+                    };
+                },
+                Some((result, full, started)) => {
+                    self.state = StreamState::Waiting(Delay::new(Instant::now() + self.watcher.interval));
+                    let events = self.watcher.apply(result?, full, started);
+                    self.pending.extend(events);
+                }
+            }
+        }
+    }
+}